@@ -1,5 +1,6 @@
 use std::{io::Result, path::PathBuf};
 use cairo_proto_build::Config;
+use cairo_proto_serde::configuration::JsonMapping;
 use clap::Parser;
 use scarb_metadata::MetadataCommand;
 use scarb_ui::args::PackagesFilter;
@@ -24,6 +25,15 @@ struct Args {
 
     #[structopt(long)]
     oracle_lock: Option<PathBuf>,
+
+    /// JSON mapping used with the oracle server, `cairo` (default) or `proto3`.
+    #[arg(long)]
+    json_mapping: Option<JsonMapping>,
+
+    /// Comma-separated services, or methods as `Service.method`, whose generated functions
+    /// return `Result<Response, OracleError>`.
+    #[arg(long, value_delimiter = ',', value_name = "SERVICE[.METHOD]")]
+    fallible: Option<Vec<String>>,
 }
 
 fn main() -> Result<()> {
@@ -48,10 +58,26 @@ fn main() -> Result<()> {
     let lock_output = absolute_path(&package, args.oracle_lock, "oracle_lock", Some(PathBuf::from("Oracle.lock")))
         .expect("lock path must be provided either as an argument (--oracle-lock src) or in the Scarb.toml file in the [tool.hints] section.");
 
+    let json_mapping = args.json_mapping.or_else(|| {
+        package.tool_metadata("hints").and_then(|tool_config| {
+            tool_config["json_mapping"].as_str().map(|s| s.parse().expect("json_mapping in the [tool.hints] section must be either `cairo` or `proto3`."))
+        })
+    }).unwrap_or_default();
+
+    let fallible = args.fallible.or_else(|| {
+        package.tool_metadata("hints").and_then(|tool_config| {
+            tool_config["fallible"].as_array().map(|fallible| {
+                fallible.iter().map(|name| name.as_str().expect("fallible in the [tool.hints] section must be an array of services or methods as `Service.method`.").to_string()).collect()
+            })
+        })
+    }).unwrap_or_default();
+
     Config::new()
         .out_dir(cairo_output)
         .oracle_module(&oracle_module)
         .oracle_lock(lock_output)
+        .json_mapping(json_mapping)
+        .fallible(fallible)
         .compile_protos(
             &[&definitions], 
             &[includes]
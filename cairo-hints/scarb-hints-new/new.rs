@@ -140,6 +140,10 @@ fn mk(
                     3. Typescript: `cd ts; npm install; npm start`
                 2. Run `scarb hints-run --oracle-server http://127.0.0.1:3000 --layout all_cairo`
 
+                On Unix, the Rust server can listen on a Unix domain socket instead of a TCP port:
+                start it with `cd rust; ORACLE_SOCKET=/tmp/oracle.sock cargo run` and run
+                `scarb hints-run --oracle-server unix:///tmp/oracle.sock --layout all_cairo`.
+
                 ## Extra options
 
                 If the circuit requires built-ins, it's possible to add the flag `--layout <VALUE>`
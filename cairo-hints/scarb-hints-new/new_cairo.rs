@@ -90,7 +90,7 @@ pub fn mk_cairo(canonical_path: &Utf8PathBuf, name: &PackageName, config: &Confi
                     fn sqrt(arg: super::oracle::Request) -> super::oracle::Response {
                         let mut serialized = ArrayTrait::new();
                         arg.serialize(ref serialized);
-                        let mut result = cheatcode::<'sqrt'>(serialized.span());
+                        let mut result = cheatcode::<'SqrtOracle/sqrt'>(serialized.span());
                         Serde::deserialize(ref result).unwrap()
                     }
                 }
@@ -133,7 +133,7 @@ pub fn mk_cairo(canonical_path: &Utf8PathBuf, name: &PackageName, config: &Confi
         fsx::write(
             &filename,
             indoc! {r#"
-                {"enums":{},"messages":{"oracle::Request":[{"name":"n","ty":{"primitive":"u64"}}],"oracle::Response":[{"name":"n","ty":{"primitive":"u64"}}]},"services":{"SqrtOracle":{"sqrt":{"input":{"message":"oracle::Request"},"output":{"message":"oracle::Response"}}}}}
+                {"enums":{},"messages":{"oracle::Request":[{"name":"n","ty":{"primitive":"u64"},"tag":{"number":1}}],"oracle::Response":[{"name":"n","ty":{"primitive":"u64"},"tag":{"number":1}}]},"services":{"SqrtOracle":{"sqrt":{"input":{"message":"oracle::Request"},"output":{"message":"oracle::Response"},"grpc_path":"/oracle.SqrtOracle/Sqrt"}}}}
             "#},
         )?;
     }
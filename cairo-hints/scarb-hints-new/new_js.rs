@@ -48,7 +48,7 @@ pub fn mk_js(canonical_path: &Utf8PathBuf, name: &PackageName, _config: &Config)
 
                 app.use(express.json());
 
-                app.post('/sqrt', (req, res) => {
+                app.post('/SqrtOracle/sqrt', (req, res) => {
                     console.dir(`received payload ${JSON.stringify(req.body)}`);
                     n = Math.sqrt(req.body.n);
                     res.statusCode = 200;
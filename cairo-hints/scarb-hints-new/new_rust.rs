@@ -55,9 +55,27 @@ pub fn mk_rust(canonical_path: &Utf8PathBuf, name: &PackageName, _config: &Confi
                         .init();
 
                     let app = Router::new()
-                        .route("/sqrt", post(root))
+                        .route("/SqrtOracle/sqrt", post(root))
                         .layer(TraceLayer::new_for_http());
 
+                    // Listen on a Unix domain socket instead of a TCP port, for
+                    // `--oracle-server unix:///path/to/socket`.
+                    #[cfg(unix)]
+                    if let Ok(path) = std::env::var("ORACLE_SOCKET") {
+                        use std::os::unix::fs::FileTypeExt;
+
+                        // Remove the socket left by a previous run, but never another file.
+                        if std::fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                            std::fs::remove_file(&path)
+                                .unwrap_or_else(|err| panic!("Failed to remove socket {path}: {err}"));
+                        }
+                        let listener = tokio::net::UnixListener::bind(&path)
+                            .unwrap_or_else(|err| panic!("Failed to bind to socket {path}: {err}"));
+                        debug!("Server started on unix://{path}");
+                        axum::serve(listener, app).await.unwrap();
+                        return;
+                    }
+
                     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
                         .await
                         .expect("Failed to bind to port 3000, port already in use by another process. Change the port or terminate the other process.");
@@ -105,11 +123,11 @@ pub fn mk_rust(canonical_path: &Utf8PathBuf, name: &PackageName, _config: &Confi
                 edition = "2021"
 
                 [dependencies]
-                axum = "0.7.3"
+                axum = "0.8.1"
                 serde = {{ version = "1.0.195", features = ["serde_derive"] }}
                 serde_repr = "0.1.18"
                 tokio = "1.35.1"
-                tower-http = {{ version = "0.5.0", features = ["trace"] }}
+                tower-http = {{ version = "0.6.1", features = ["trace"] }}
                 tracing = "0.1.40"
                 tracing-subscriber = "0.3.18"
                 prost = "0.12.3"
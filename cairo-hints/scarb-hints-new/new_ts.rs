@@ -84,7 +84,7 @@ pub fn mk_ts(canonical_path: &Utf8PathBuf, name: &PackageName, _config: &Config)
 
                     app.use(express.json());
 
-                    app.post('/sqrt', (req: Request, res: Response) => {
+                    app.post('/SqrtOracle/sqrt', (req: Request, res: Response) => {
                         console.dir(`received payload ${JSON.stringify(req.body)}`);
                         const n = Math.sqrt(req.body.n);
                         res.statusCode = 200;
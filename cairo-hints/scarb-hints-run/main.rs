@@ -7,6 +7,7 @@ use std::{
 
 use anyhow::{Context, Result};
 use cairo_lang_sierra::program::VersionedProgram;
+use cairo_oracle::OracleTransport;
 use cairo_oracle_hint_processor::{run_1, Error, FuncArg, FuncArgs};
 use cairo_vm::types::layout_name::LayoutName;
 use cairo_vm::Felt252;
@@ -15,7 +16,7 @@ use clap::Parser;
 use itertools::Itertools;
 use scarb_metadata::{MetadataCommand, ScarbCommand};
 use scarb_ui::args::PackagesFilter;
-use scarb_utils::absolute_path;
+use scarb_utils::{absolute_path, OracleArgs};
 
 mod deserialization;
 
@@ -37,9 +38,8 @@ struct Args {
     #[arg(long, default_value_t = false)]
     proof_mode: bool,
 
-    /// Oracle server URL.
-    #[arg(long)]
-    oracle_server: Option<String>,
+    #[command(flatten)]
+    oracle: OracleArgs,
 
     /// Oracle lock file path.
     #[arg(long)]
@@ -159,6 +159,15 @@ fn main() -> Result<(), Error> {
     let lock_file = File::open(lock_output).map_err(|e| Error::IO(e))?;
     let reader = BufReader::new(lock_file);
     let service_configuration = serde_json::from_reader(reader).map_err(|e| Error::IO(e.into()))?;
+    let oracle = args
+        .oracle
+        .oracle(
+            &package,
+            service_configuration,
+            &Utf8PathBuf::from(scarb_target_dir),
+            true,
+        )
+        .map_err(Error::Oracle)?;
 
     let sierra_program = serde_json::from_str::<VersionedProgram>(
         &fs::read_to_string(path.clone())
@@ -173,9 +182,15 @@ fn main() -> Result<(), Error> {
 
     let sierra_program = sierra_program.program;
 
+    // Fails if the run didn't call every interaction of a replayed cassette.
+    let finish = || {
+        oracle
+            .as_ref()
+            .map_or(Ok(()), |oracle| oracle.finish())
+            .map_err(Error::UnreplayedInteractions)
+    };
     match run_1(
-        &service_configuration,
-        &args.oracle_server,
+        oracle.as_ref(),
         &str_into_layout(&args.layout),
         &args.trace_file,
         &args.memory_file,
@@ -191,7 +206,7 @@ fn main() -> Result<(), Error> {
                     return_values.iter().map(|m| m.to_string()).join(", ");
                 println!("Return values : [{}]", return_values_string_list);
             }
-            Ok(())
+            finish()
         }
         Err(Error::RunPanic(panic_data)) => {
             if !panic_data.is_empty() {
@@ -209,7 +224,7 @@ fn main() -> Result<(), Error> {
                     .join(", ");
                 println!("Run panicked with: [{}]", panic_data_string_list);
             }
-            Ok(())
+            finish()
         }
         Err(err) => Err(err),
     }
@@ -3,14 +3,15 @@ use std::io::BufReader;
 use std::path::PathBuf;
 use std::{env, fs};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use cairo_lang_hints_test_runner::{CompiledTestRunner, TestRunConfig};
 use cairo_lang_test_plugin::TestCompilation;
+use cairo_oracle::OracleTransport;
 use cairo_vm::types::layout_name::LayoutName;
 use clap::Parser;
 use scarb_metadata::{Metadata, MetadataCommand, PackageMetadata, ScarbCommand, TargetMetadata};
 use scarb_ui::args::PackagesFilter;
-use scarb_utils::absolute_path;
+use scarb_utils::{absolute_path, OracleArgs};
 
 /// Execute all unit tests of a local package.
 #[derive(Parser, Clone, Debug)]
@@ -31,9 +32,8 @@ struct Args {
     #[arg(long, default_value_t = false)]
     ignored: bool,
 
-    /// Oracle server URL.
-    #[arg(long)]
-    oracle_server: Option<String>,
+    #[command(flatten)]
+    oracle: OracleArgs,
 
     #[arg(long)]
     oracle_lock: Option<PathBuf>,
@@ -89,11 +89,8 @@ fn main() -> Result<()> {
 
     let profile = env::var("SCARB_PROFILE").unwrap_or("dev".into());
     let default_target_dir = metadata.runtime_manifest.join("target");
-    let target_dir = metadata
-        .target_dir
-        .clone()
-        .unwrap_or(default_target_dir)
-        .join(profile);
+    let root_target_dir = metadata.target_dir.clone().unwrap_or(default_target_dir);
+    let target_dir = root_target_dir.join(profile);
 
     for package in matched {
         println!("testing {} ...", package.name);
@@ -103,6 +100,12 @@ fn main() -> Result<()> {
         let lock_file = File::open(lock_output)?;
         let reader = BufReader::new(lock_file);
         let service_config = serde_json::from_reader(reader)?;
+        // The oracle is shared by all the test targets of the package, whose tests are run in
+        // parallel.
+        let oracle = args
+            .oracle
+            .oracle(&package, service_config, &root_target_dir, false)
+            .map_err(|err| anyhow!(err))?;
 
         for target in find_testable_targets(&package) {
             let file_path = target_dir.join(format!("{}.test.json", target.name.clone()));
@@ -118,13 +121,14 @@ fn main() -> Result<()> {
                 ignored: args.ignored,
             };
             let runner = CompiledTestRunner::new(test_compilation, config);
-            runner.run(
-                &args.oracle_server,
-                &service_config,
-                &str_into_layout(&args.layout),
-            )?;
+            runner.run(oracle.as_ref(), &str_into_layout(&args.layout))?;
             println!();
         }
+        if let Some(oracle) = &oracle {
+            oracle.finish().map_err(|err| {
+                anyhow!("Oracle cassette has interactions that were not replayed: {err}")
+            })?;
+        }
     }
 
     Ok(())
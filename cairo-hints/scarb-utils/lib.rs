@@ -1,5 +1,13 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
+use cairo_oracle::{
+    CachingTransport, CairoOracle, OracleOptions, OracleRouter, OracleTransport,
+    RecordingTransport, ReplayTransport, SubprocessTransport, WasmEncoding, WasmTransport,
+};
+use cairo_proto_serde::configuration::Configuration;
+use camino::Utf8Path;
+use clap::Args;
 use scarb_metadata::PackageMetadata;
 
 pub fn absolute_path(
@@ -31,3 +39,408 @@ pub fn absolute_path(
         Some(project_dir.join(definitions))
     }
 }
+
+/// Oracle options shared by `scarb hints-run` and `scarb hints-test`.
+#[derive(Args, Clone, Debug)]
+pub struct OracleArgs {
+    /// Oracle server URL.
+    #[arg(long)]
+    pub oracle_server: Option<String>,
+
+    /// Oracle server URL of the hints of a service, instead of the oracle. Can be repeated.
+    #[arg(long = "oracle-service-server", value_name = "SERVICE=URL")]
+    pub oracle_service_servers: Vec<String>,
+
+    /// Command of an oracle answering hints over its standard input and output.
+    #[arg(long, conflicts_with_all = ["oracle_server", "oracle_wasm"])]
+    pub oracle_command: Option<String>,
+
+    /// WebAssembly module answering hints in-process.
+    #[arg(long, conflicts_with = "oracle_server")]
+    pub oracle_wasm: Option<PathBuf>,
+
+    /// Encoding of the messages exchanged with the WebAssembly module, `json` (default) or
+    /// `protobuf`.
+    #[arg(long)]
+    pub oracle_wasm_encoding: Option<WasmEncoding>,
+
+    /// Maximum duration of each call to the oracle server in seconds, 30 by default.
+    #[arg(long)]
+    pub oracle_timeout: Option<u64>,
+
+    /// Number of times a call to the oracle server is retried when it fails to connect, times
+    /// out or is answered with a retryable status, 0 by default.
+    #[arg(long)]
+    pub oracle_retries: Option<u32>,
+
+    /// Delay before the first retry in milliseconds, doubled before each of the following ones,
+    /// 500 by default.
+    #[arg(long)]
+    pub oracle_retry_backoff: Option<u64>,
+
+    /// Comma-separated HTTP status codes of the oracle server responses to retry,
+    /// 429,502,503,504 by default.
+    #[arg(long, value_delimiter = ',')]
+    pub oracle_retry_statuses: Option<Vec<u16>>,
+
+    /// Header sent to the oracle server, where `${VAR}` is replaced with the environment
+    /// variable `VAR`, e.g. `Authorization: Bearer ${ORACLE_TOKEN}`. Can be repeated.
+    #[arg(long = "oracle-header", value_name = "NAME: VALUE")]
+    pub oracle_headers: Vec<String>,
+
+    /// PEM file of the certificate authorities trusted for the oracle server, in addition to
+    /// the system ones.
+    #[arg(long)]
+    pub oracle_ca_cert: Option<PathBuf>,
+
+    /// PEM file of the client certificate presented to the oracle server.
+    #[arg(long, requires = "oracle_client_key")]
+    pub oracle_client_cert: Option<PathBuf>,
+
+    /// PEM file of the PKCS #8 private key of the client certificate.
+    #[arg(long, requires = "oracle_client_cert")]
+    pub oracle_client_key: Option<PathBuf>,
+
+    /// Cassette to which the hints answered by the oracle are recorded.
+    #[arg(long, conflicts_with = "oracle_replay")]
+    pub oracle_record: Option<PathBuf>,
+
+    /// Cassette answering the hints recorded with `--oracle-record`, instead of an oracle.
+    #[arg(long, conflicts_with_all = ["oracle_server", "oracle_service_servers", "oracle_command", "oracle_wasm"])]
+    pub oracle_replay: Option<PathBuf>,
+
+    /// Comma-separated services, or methods as `Service.method`, whose hints are cached in the
+    /// target directory.
+    #[arg(long, value_delimiter = ',', value_name = "SERVICE[.METHOD]")]
+    pub oracle_cache: Option<Vec<String>>,
+
+    /// Call the oracle for every hint, even for the cacheable methods in Scarb.toml.
+    #[arg(long, conflicts_with = "oracle_cache")]
+    pub no_oracle_cache: bool,
+}
+
+impl OracleArgs {
+    /// Oracle answering the hints of `package`: the oracle given as an argument, or else the
+    /// `oracle_command` or `oracle_wasm` in the Scarb.toml file in the [tool.hints] section.
+    /// The hints of the services with their own server, given as arguments or in the
+    /// [tool.hints.servers] section, are sent to that server instead.
+    /// With `--oracle-replay`, the hints are answered from the cassette instead, in the order
+    /// they were recorded if `ordered`, and with `--oracle-record`, the hints answered by the
+    /// oracle are recorded.
+    ///
+    /// The hints of the cacheable methods are cached in `target_dir/oracle-cache`, and answered
+    /// from there as long as the oracle configuration doesn't change.
+    ///
+    /// Commands are run, and WebAssembly modules and cassettes are looked up, in the package
+    /// directory.
+    pub fn oracle(
+        &self,
+        package: &PackageMetadata,
+        configuration: Configuration,
+        target_dir: &Utf8Path,
+        ordered: bool,
+    ) -> Result<Option<Box<dyn OracleTransport + Send + Sync>>, Box<str>> {
+        let project_dir = package
+            .manifest_path
+            .parent()
+            .expect("manifest path has a parent")
+            .as_std_path();
+        if let Some(cassette) = &self.oracle_replay {
+            let mut transport = ReplayTransport::open(project_dir.join(cassette))?;
+            if !ordered {
+                transport = transport.unordered();
+            }
+            return Ok(Some(Box::new(transport)));
+        }
+
+        let cacheable = self.cacheable(package)?;
+        let oracle = match self.transport(package, configuration.clone())? {
+            Some(oracle) if !cacheable.is_empty() => {
+                let cache_dir = target_dir.join("oracle-cache");
+                let transport =
+                    CachingTransport::new(oracle, cache_dir, &configuration, &cacheable)?;
+                Some(Box::new(transport) as Box<dyn OracleTransport + Send + Sync>)
+            }
+            oracle => oracle,
+        };
+        match (oracle, &self.oracle_record) {
+            (Some(oracle), Some(cassette)) => {
+                let transport = RecordingTransport::create(oracle, project_dir.join(cassette))?;
+                Ok(Some(Box::new(transport)))
+            }
+            (None, Some(_)) => Err(Box::from(
+                "--oracle-record needs an oracle whose hints are recorded, given with --oracle-server, --oracle-command or --oracle-wasm",
+            )),
+            (oracle, None) => Ok(oracle),
+        }
+    }
+
+    /// Services and methods whose hints are cached: the ones given as an argument, or else the
+    /// `oracle_cache` array in the Scarb.toml file in the [tool.hints] section.
+    fn cacheable(&self, package: &PackageMetadata) -> Result<Vec<String>, Box<str>> {
+        if self.no_oracle_cache {
+            return Ok(Vec::new());
+        }
+        if let Some(cacheable) = &self.oracle_cache {
+            return Ok(cacheable.clone());
+        }
+
+        let Some(cacheable) = package
+            .tool_metadata("hints")
+            .and_then(|tool_config| tool_config.get("oracle_cache"))
+        else {
+            return Ok(Vec::new());
+        };
+        cacheable
+            .as_array()
+            .and_then(|cacheable| {
+                cacheable
+                    .iter()
+                    .map(|name| name.as_str().map(String::from))
+                    .collect()
+            })
+            .ok_or_else(|| {
+                Box::from(
+                    "oracle_cache in [tool.hints] must be an array of services or methods as `Service.method`",
+                )
+            })
+    }
+
+    fn transport(
+        &self,
+        package: &PackageMetadata,
+        configuration: Configuration,
+    ) -> Result<Option<Box<dyn OracleTransport + Send + Sync>>, Box<str>> {
+        let servers = self.service_servers(package)?;
+        let oracle = self.default_transport(package, configuration.clone())?;
+        if servers.is_empty() {
+            return Ok(oracle);
+        }
+
+        let options = self.options(package)?;
+        let mut router = OracleRouter::new(configuration.clone(), oracle);
+        for (service, server) in servers {
+            let oracle = CairoOracle::with_options(&server, configuration.clone(), &options)?;
+            router.route(&service, oracle)?;
+        }
+        Ok(Some(Box::new(router)))
+    }
+
+    /// Server URL of each service in the Scarb.toml file in the [tool.hints.servers] section,
+    /// and given as arguments, replacing those of the same service.
+    fn service_servers(
+        &self,
+        package: &PackageMetadata,
+    ) -> Result<Vec<(String, String)>, Box<str>> {
+        let mut servers = Vec::new();
+        if let Some(tool_servers) = package
+            .tool_metadata("hints")
+            .and_then(|tool_config| tool_config.get("servers"))
+        {
+            let tool_servers = tool_servers.as_object().ok_or(
+                "servers in [tool.hints] must be a table of service names and server URLs",
+            )?;
+            for (service, server) in tool_servers {
+                let server = server
+                    .as_str()
+                    .ok_or_else(|| format!("servers.{service} in [tool.hints] must be a string"))?;
+                servers.push((service.clone(), server.to_string()));
+            }
+        }
+        for service_server in &self.oracle_service_servers {
+            let (service, server) = service_server.split_once('=').ok_or_else(|| {
+                format!("oracle-service-server must be formatted as `SERVICE=URL`, got {service_server:?}")
+            })?;
+            let service = service.trim();
+            servers.retain(|(other, _)| other != service);
+            servers.push((service.to_string(), server.trim().to_string()));
+        }
+        Ok(servers)
+    }
+
+    fn default_transport(
+        &self,
+        package: &PackageMetadata,
+        configuration: Configuration,
+    ) -> Result<Option<Box<dyn OracleTransport + Send + Sync>>, Box<str>> {
+        if let Some(server) = &self.oracle_server {
+            let options = self.options(package)?;
+            return Ok(Some(Box::new(CairoOracle::with_options(
+                server,
+                configuration,
+                &options,
+            )?)));
+        }
+
+        let project_dir = package
+            .manifest_path
+            .parent()
+            .expect("manifest path has a parent");
+        let tool_config = |key: &str| {
+            package
+                .tool_metadata("hints")
+                .and_then(|tool_config| tool_config[key].as_str().map(String::from))
+        };
+
+        let oracle_command = self.oracle_command.clone().or_else(|| {
+            self.oracle_wasm
+                .is_none()
+                .then(|| tool_config("oracle_command"))
+                .flatten()
+        });
+        if let Some(oracle_command) = oracle_command {
+            let options = self.options(package)?;
+            let transport = SubprocessTransport::spawn_command_line(
+                &oracle_command,
+                project_dir,
+                configuration,
+            )?
+            .with_timeout(options.timeout);
+            return Ok(Some(Box::new(transport)));
+        }
+
+        if let Some(oracle_wasm) =
+            absolute_path(package, self.oracle_wasm.clone(), "oracle_wasm", None)
+        {
+            let encoding = match self.oracle_wasm_encoding {
+                Some(encoding) => encoding,
+                None => tool_config("oracle_wasm_encoding")
+                    .map(|encoding| encoding.parse())
+                    .transpose()?
+                    .unwrap_or_default(),
+            };
+            let transport = WasmTransport::from_file(oracle_wasm, encoding, configuration)?;
+            return Ok(Some(Box::new(transport)));
+        }
+
+        Ok(None)
+    }
+
+    /// Timeout, retry policy, headers and TLS options of the calls to the oracle server: the
+    /// ones given as arguments, or else the `oracle_timeout`, `oracle_retries`,
+    /// `oracle_retry_backoff`, `oracle_retry_statuses`, `oracle_headers`, `oracle_ca_cert`,
+    /// `oracle_client_cert` and `oracle_client_key` in the Scarb.toml file in the [tool.hints]
+    /// section.
+    ///
+    /// Headers given as arguments are added to the ones in Scarb.toml, replacing those with the
+    /// same name.
+    pub fn options(&self, package: &PackageMetadata) -> Result<OracleOptions, Box<str>> {
+        let tool_config = |key: &str| {
+            package
+                .tool_metadata("hints")
+                .and_then(|tool_config| tool_config.get(key))
+        };
+        let number = |arg: Option<u64>, key: &str| match arg {
+            Some(arg) => Ok(Some(arg)),
+            None => tool_config(key)
+                .map(|value| {
+                    value.as_u64().ok_or_else(|| {
+                        Box::<str>::from(format!(
+                            "{key} in [tool.hints] must be a non-negative integer"
+                        ))
+                    })
+                })
+                .transpose(),
+        };
+
+        let mut options = OracleOptions::default();
+        if let Some(timeout) = number(self.oracle_timeout, "oracle_timeout")? {
+            options.timeout = Duration::from_secs(timeout);
+        }
+        if let Some(retries) = number(self.oracle_retries.map(u64::from), "oracle_retries")? {
+            options.retry_policy.retries = u32::try_from(retries)
+                .map_err(|_| Box::<str>::from("oracle_retries in [tool.hints] is too large"))?;
+        }
+        if let Some(backoff) = number(self.oracle_retry_backoff, "oracle_retry_backoff")? {
+            options.retry_policy.backoff = Duration::from_millis(backoff);
+        }
+
+        let retry_statuses = match &self.oracle_retry_statuses {
+            Some(retry_statuses) => Some(retry_statuses.clone()),
+            None => tool_config("oracle_retry_statuses")
+                .map(|retry_statuses| {
+                    retry_statuses
+                        .as_array()
+                        .and_then(|retry_statuses| {
+                            retry_statuses
+                                .iter()
+                                .map(|status| status.as_u64()?.try_into().ok())
+                                .collect()
+                        })
+                        .ok_or_else(|| {
+                            Box::<str>::from(
+                                "oracle_retry_statuses in [tool.hints] must be an array of HTTP status codes",
+                            )
+                        })
+                })
+                .transpose()?,
+        };
+        if let Some(retry_statuses) = retry_statuses {
+            options.retry_policy.retry_statuses = retry_statuses;
+        }
+
+        if let Some(headers) = tool_config("oracle_headers") {
+            let headers = headers.as_object().ok_or(
+                "oracle_headers in [tool.hints] must be a table of header names and values",
+            )?;
+            for (name, value) in headers {
+                let value = value.as_str().ok_or_else(|| {
+                    format!("oracle_headers.{name} in [tool.hints] must be a string")
+                })?;
+                options
+                    .headers
+                    .push((name.clone(), expand_env_vars(value)?));
+            }
+        }
+        for header in &self.oracle_headers {
+            let (name, value) = header.split_once(':').ok_or_else(|| {
+                format!("oracle-header must be formatted as `NAME: VALUE`, got {header:?}")
+            })?;
+            let name = name.trim();
+            options
+                .headers
+                .retain(|(other, _)| !other.eq_ignore_ascii_case(name));
+            options
+                .headers
+                .push((name.to_string(), expand_env_vars(value.trim())?));
+        }
+
+        options.ca_certificate =
+            absolute_path(package, self.oracle_ca_cert.clone(), "oracle_ca_cert", None);
+        // The certificate and key are both given as arguments, or both taken from Scarb.toml.
+        options.client_certificate = absolute_path(
+            package,
+            self.oracle_client_cert.clone(),
+            "oracle_client_cert",
+            None,
+        );
+        options.client_key = absolute_path(
+            package,
+            self.oracle_client_key.clone(),
+            "oracle_client_key",
+            None,
+        );
+
+        Ok(options)
+    }
+}
+
+/// Replaces the `${VAR}` in `value` with the value of the environment variable `VAR`, so that
+/// secrets can be kept out of Scarb.toml.
+fn expand_env_vars(value: &str) -> Result<String, Box<str>> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let len = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("Unterminated ${{ in {value:?}"))?;
+        let name = &rest[start + 2..start + len];
+        let var = std::env::var(name)
+            .map_err(|_| format!("Environment variable {name} used in {value:?} is not set"))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&var);
+        rest = &rest[start + len + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
@@ -15,8 +15,8 @@ use cairo_lang_test_plugin::{
     compile_test_prepared_db, test_plugin_suite, TestCompilation, TestCompilationMetadata,
     TestConfig, TestsCompilationConfig,
 };
+use cairo_oracle::OracleTransport;
 use cairo_oracle_hint_processor::{run_1, Error, FuncArgs};
-use cairo_proto_serde::configuration::Configuration;
 use cairo_vm::types::layout_name::LayoutName;
 use cairo_vm::Felt252 as VMFelt;
 use colored::Colorize;
@@ -48,14 +48,13 @@ impl TestRunner {
     }
 
     /// Runs the tests and process the results for a summary.
-    pub fn run(
+    pub fn run<T: OracleTransport + Sync>(
         &self,
-        oracle_server: &Option<String>,
-        configuration: &Configuration,
+        oracle: Option<T>,
         layout: &LayoutName,
     ) -> Result<Option<TestsSummary>> {
         let runner = CompiledTestRunner::new(self.compiler.build()?, self.config.clone());
-        runner.run(oracle_server, configuration, layout)
+        runner.run(oracle, layout)
     }
 }
 
@@ -76,10 +75,9 @@ impl CompiledTestRunner {
     }
 
     /// Execute preconfigured test execution.
-    pub fn run(
+    pub fn run<T: OracleTransport + Sync>(
         self,
-        oracle_server: &Option<String>,
-        configuration: &Configuration,
+        oracle: Option<T>,
         layout: &LayoutName,
     ) -> Result<Option<TestsSummary>> {
         let (compiled, filtered_out) = filter_test_cases(
@@ -98,8 +96,7 @@ impl CompiledTestRunner {
             compiled.sierra_program.program,
             // compiled.function_set_costs,
             // compiled.contracts_info,
-            oracle_server,
-            configuration,
+            oracle,
             layout,
         )?;
 
@@ -304,13 +301,12 @@ fn is_equal_vec_felt(a: &Vec<VMFelt>, b: &Vec<Felt252>) -> bool {
 }
 
 /// Runs the tests and process the results for a summary.
-pub fn run_tests(
+pub fn run_tests<T: OracleTransport + Sync>(
     named_tests: Vec<(String, TestConfig)>,
     sierra_program: Program,
     // _function_set_costs: OrderedHashMap<FunctionId, OrderedHashMap<CostTokenType, i32>>,
     // _contracts_info: OrderedHashMap<Felt252, ContractInfo>,
-    oracle_server: &Option<String>,
-    configuration: &Configuration,
+    oracle: Option<T>,
     layout: &LayoutName,
 ) -> Result<TestsSummary> {
     println!("running {} tests", named_tests.len());
@@ -329,8 +325,7 @@ pub fn run_tests(
                 }
 
                 let r = run_1(
-                    configuration,
-                    oracle_server,
+                    oracle.as_ref(),
                     layout,
                     &None,
                     &None,
@@ -37,7 +37,7 @@ use cairo_lang_sierra_type_size::get_type_size_map;
 use cairo_lang_utils::{
     bigint::BigIntAsHex, casts::IntoOrPanic, unordered_hash_map::UnorderedHashMap,
 };
-use cairo_proto_serde::configuration::Configuration;
+use cairo_oracle::OracleTransport;
 use cairo_vm::{
     hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
     serde::deserialize_program::{ApTracking, FlowTrackingData, HintParams, ReferenceManager},
@@ -231,11 +231,10 @@ fn result_inner_type_size(
 /// Returns the runner after execution + the return values + the serialized return values (if serialize_output is enabled)
 /// The return values will contain the memory values just as they appear in the VM, after removing the PanicResult enum (if present).
 /// Except if either the flag append_return_values or proof_mode are enabled, in which case the return values will consist of its serialized form: [array_len, array[0], array[1], ..., array[array_len -1]]
-pub fn cairo_run_program(
+pub fn cairo_run_program<T: OracleTransport>(
     sierra_program: &SierraProgram,
     cairo_run_config: Cairo1RunConfig,
-    service_config: &Configuration,
-    oracle_server: &Option<String>,
+    oracle: Option<T>,
     entry_func_name: &str,
 ) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
     let metadata = calc_metadata_ap_change_only(sierra_program)
@@ -313,7 +312,7 @@ pub fn cairo_run_program(
         RunResources::default(),
         cairo_run_config.copy_to_output(),
     );
-    let mut hint_processor = Rpc1HintProcessor::new(hint_processor, oracle_server, service_config);
+    let mut hint_processor = Rpc1HintProcessor::new(hint_processor, oracle);
 
     let data: Vec<MaybeRelocatable> = instructions
         .flat_map(|inst| inst.assemble().encode())
@@ -9,7 +9,7 @@ use cairo_lang_sierra::program::Program as SierraProgram;
 use cairo_lang_sierra::program_registry::ProgramRegistryError;
 use cairo_lang_sierra_to_casm::compiler::CompilationError;
 use cairo_lang_sierra_to_casm::metadata::MetadataError;
-use cairo_proto_serde::configuration::Configuration;
+use cairo_oracle::OracleTransport;
 use cairo_run::Cairo1RunConfig;
 use cairo_vm::air_public_input::PublicInputError;
 use cairo_vm::cairo_run::EncodeTraceError;
@@ -56,6 +56,10 @@ pub enum Error {
     Program(#[from] ProgramError),
     #[error(transparent)]
     Memory(#[from] MemoryError),
+    #[error("Failed to set up the oracle: {0}")]
+    Oracle(Box<str>),
+    #[error("Oracle cassette has interactions that were not replayed: {0}")]
+    UnreplayedInteractions(Box<str>),
     #[error("Program panicked with {0:?}")]
     RunPanic(Vec<Felt252>),
     #[error("Function signature has no return types")]
@@ -124,9 +128,8 @@ impl FileWriter {
     }
 }
 
-pub fn run_1(
-    service_config: &Configuration,
-    oracle_server: &Option<String>,
+pub fn run_1<T: OracleTransport>(
+    oracle: Option<T>,
     layout: &LayoutName,
     trace_file: &Option<PathBuf>,
     memory_file: &Option<PathBuf>,
@@ -155,13 +158,8 @@ pub fn run_1(
         append_return_values: false,
     };
 
-    let (runner, return_values, _) = cairo_run::cairo_run_program(
-        &sierra_program,
-        cairo_run_config,
-        service_config,
-        oracle_server,
-        entry_func_name,
-    )?;
+    let (runner, return_values, _) =
+        cairo_run::cairo_run_program(&sierra_program, cairo_run_config, oracle, entry_func_name)?;
 
     if let Some(trace_path) = trace_file {
         let relocated_trace = runner
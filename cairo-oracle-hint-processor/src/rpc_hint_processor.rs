@@ -5,8 +5,7 @@ use cairo_lang_casm::{
     operand::{CellRef, ResOperand},
 };
 use cairo_lang_utils::bigint::BigIntAsHex;
-use cairo_oracle::CairoOracle;
-use cairo_proto_serde::configuration::Configuration;
+use cairo_oracle::{CairoOracle, OracleTransport};
 use cairo_vm::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
 use cairo_vm::hint_processor::hint_processor_definition::HintProcessorLogic;
 use cairo_vm::hint_processor::hint_processor_definition::HintReference;
@@ -26,23 +25,17 @@ use cairo_vm::{
 use core::any::Any;
 use std::collections::HashMap;
 
-/// HintProcessor for Cairo 1 compiler hints.
-pub struct Rpc1HintProcessor<'a> {
+/// HintProcessor for Cairo 1 compiler hints, answering cheatcodes with an oracle transport.
+pub struct Rpc1HintProcessor<T: OracleTransport = CairoOracle> {
     inner_processor: Cairo1HintProcessor,
-    server: Option<String>,
-    configuration: &'a Configuration,
+    oracle: Option<T>,
 }
 
-impl<'a> Rpc1HintProcessor<'a> {
-    pub fn new(
-        inner_processor: Cairo1HintProcessor,
-        server: &Option<String>,
-        configuration: &'a Configuration,
-    ) -> Self {
+impl<T: OracleTransport> Rpc1HintProcessor<T> {
+    pub fn new(inner_processor: Cairo1HintProcessor, oracle: Option<T>) -> Self {
         Self {
             inner_processor,
-            server: server.clone(),
-            configuration,
+            oracle,
         }
     }
 
@@ -70,10 +63,11 @@ impl<'a> Rpc1HintProcessor<'a> {
         let res_segment_start = res_segment.ptr;
 
 
-        let oracle_url = self.server.as_ref().expect(
-            format!("Please provide an --oracle-server argument to execute hints").as_str(),
-        );
-        let oracle = CairoOracle::new(oracle_url.clone(), self.configuration.clone());
+        let oracle = self.oracle.as_ref().ok_or_else(|| {
+            HintError::CustomHint(Box::from(
+                "Please provide an --oracle-server argument to execute hints",
+            ))
+        })?;
         let data = oracle.execute_hint(selector, inputs.as_ref()).map_err(|err| HintError::CustomHint(err))?;
 
         res_segment.write_data(data.iter())?;
@@ -85,7 +79,7 @@ impl<'a> Rpc1HintProcessor<'a> {
     }
 }
 
-impl<'a> HintProcessorLogic for Rpc1HintProcessor<'a> {
+impl<T: OracleTransport> HintProcessorLogic for Rpc1HintProcessor<T> {
     // Ignores all data except for the code that should contain
     fn compile_hint(
         &self,
@@ -140,7 +134,7 @@ impl<'a> HintProcessorLogic for Rpc1HintProcessor<'a> {
     }
 }
 
-impl<'a> ResourceTracker for Rpc1HintProcessor<'a> {
+impl<T: OracleTransport> ResourceTracker for Rpc1HintProcessor<T> {
     fn consumed(&self) -> bool {
         self.inner_processor.consumed()
     }
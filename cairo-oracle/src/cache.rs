@@ -0,0 +1,307 @@
+//! Transport memoizing on disk the hints answered by another transport.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use cairo_proto_serde::configuration::Configuration;
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt as Felt252;
+use tempfile::NamedTempFile;
+
+use crate::{from_hex, method_declaration, to_hex, OracleTransport};
+
+/// Cached answer, with the felts as hexadecimal strings. The input is stored to tell apart the
+/// inputs with the same hash.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    input: Vec<String>,
+    output: Vec<String>,
+}
+
+/// 64-bit FNV-1a, as for the hashed selectors, so that the entries are found again by other
+/// builds and on other platforms.
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// File in the directory of a configuration holding it, to tell apart the configurations with
+/// the same hash.
+const CONFIGURATION_FILE: &str = "Oracle.lock";
+
+/// Answers the hints of the cacheable methods from
+/// `<dir>/<configuration hash>/<Service>/<method>/<input hash>.json` when it exists, and
+/// stores there the answers of the inner transport otherwise.
+///
+/// Entries are only reused with the same oracle configuration, so regenerating the
+/// Oracle.lock file with other contents invalidates the cache. The configuration is copied to
+/// `<dir>/<configuration hash>/Oracle.lock`.
+pub struct CachingTransport<T> {
+    inner: T,
+    dir: PathBuf,
+    configuration: Configuration,
+    /// Service and name of the cacheable methods.
+    cacheable: HashSet<(String, String)>,
+}
+
+impl<T> CachingTransport<T> {
+    /// Caches in `dir` the hints answered by `inner` for the methods named in `cacheable`,
+    /// either as `Service` for all the methods of a service or as `Service.method`.
+    pub fn new(
+        inner: T,
+        dir: impl AsRef<Path>,
+        configuration: &Configuration,
+        cacheable: &[String],
+    ) -> Result<Self, Box<str>> {
+        let mut selected = HashSet::new();
+        for name in cacheable {
+            let (service, method) = match name.split_once('.') {
+                Some((service, method)) => (service, Some(method)),
+                None => (name.as_str(), None),
+            };
+            let declared = configuration
+                .services
+                .get(service)
+                .map(|service| &service.methods)
+                .filter(|methods| method.map_or(true, |method| methods.contains_key(method)))
+                .ok_or_else(|| {
+                    format!("Cacheable oracle method {name:?} is not in the oracle configuration")
+                })?;
+            for declared in declared.keys() {
+                if method.map_or(true, |method| method == declared) {
+                    selected.insert((service.to_string(), declared.clone()));
+                }
+            }
+        }
+
+        // Maps serialize to JSON sorted by key, so that equal configurations have equal hashes.
+        let serialized = serde_json::to_value(configuration)
+            .expect("configuration can be serialized")
+            .to_string();
+        let dir = dir
+            .as_ref()
+            .join(format!("{:016x}", hash(serialized.as_bytes())));
+
+        let path = dir.join(CONFIGURATION_FILE);
+        match fs::read_to_string(&path) {
+            Ok(stored) if stored == serialized => {}
+            Ok(_) => {
+                return Err(Box::from(format!(
+                    "Oracle cache {} holds the entries of another configuration with the same hash, remove it",
+                    dir.display()
+                )))
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                write_atomically(&path, serialized.as_bytes()).map_err(|err| {
+                    format!("Couldn't write oracle cache {}: {err}", path.display())
+                })?
+            }
+            Err(err) => {
+                return Err(Box::from(format!(
+                    "Couldn't read oracle cache {}: {err}",
+                    path.display()
+                )))
+            }
+        }
+
+        Ok(Self {
+            inner,
+            dir,
+            configuration: configuration.clone(),
+            cacheable: selected,
+        })
+    }
+}
+
+impl<T: OracleTransport> OracleTransport for CachingTransport<T> {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, declaration) = method_declaration(&self.configuration, selector)?;
+        if !self
+            .cacheable
+            .contains(&(service.to_string(), method.to_string()))
+        {
+            return self.inner.execute_hint(selector, data);
+        }
+
+        let input = to_hex(data);
+        let path = self
+            .dir
+            .join(service)
+            .join(method)
+            .join(format!("{:016x}.json", hash(input.join(",").as_bytes())));
+
+        // Unreadable entries are answered again by the inner transport, and replaced.
+        let cached = fs::read(&path)
+            .ok()
+            .and_then(|entry| serde_json::from_slice::<Entry>(&entry).ok())
+            .filter(|entry| entry.input == input)
+            .and_then(|entry| from_hex(&entry.output));
+        if let Some(output) = cached {
+            return Ok(output);
+        }
+
+        let output = self.inner.execute_hint(selector, data)?;
+        // The errors of fallible methods may not happen again, so they aren't cached.
+        if declaration.fallible && output.first() == Some(&Felt252::ONE) {
+            return Ok(output);
+        }
+
+        let entry = serde_json::to_vec(&Entry {
+            input,
+            output: to_hex(&output),
+        })
+        .expect("entries can be serialized");
+        // The answer is still returned when it can't be cached.
+        if let Err(err) = write_atomically(&path, &entry) {
+            eprintln!(
+                "Couldn't write oracle cache entry {}: {err}",
+                path.display()
+            );
+        }
+
+        Ok(output)
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        self.inner.finish()
+    }
+}
+
+/// Writes `contents` to `path`, creating its directory. Each writer writes its own temporary
+/// file, renamed into place once written, so that concurrent runs and threads never read it
+/// partially written.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = path.parent().expect("cache files are in a directory");
+    fs::create_dir_all(dir)?;
+    let mut partial = NamedTempFile::new_in(dir)?;
+    partial.write_all(contents)?;
+    partial.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Barrier;
+    use std::thread;
+
+    use super::*;
+    use crate::tests::{configuration, Increment};
+
+    /// Answers with its input plus one once `barrier` is reached by all the calls.
+    struct Synchronized {
+        barrier: Barrier,
+    }
+
+    impl OracleTransport for Synchronized {
+        fn execute_hint(
+            &self,
+            _selector: &str,
+            data: &[Felt252],
+        ) -> Result<Vec<Felt252>, Box<str>> {
+            self.barrier.wait();
+            Ok(vec![data[0] + Felt252::ONE])
+        }
+    }
+
+    #[test]
+    fn it_caches_cacheable_methods() {
+        let configuration = configuration();
+        let dir = tempfile::tempdir().unwrap();
+        let cacheable = ["Oracle.inc".to_string()];
+
+        let cache =
+            CachingTransport::new(Increment::default(), &dir, &configuration, &cacheable).unwrap();
+        for _ in 0..2 {
+            let output = cache.execute_hint("inc", &[Felt252::from(41)]).unwrap();
+            assert_eq!(output, vec![Felt252::from(42)]);
+            cache.execute_hint("next", &[Felt252::from(41)]).unwrap();
+        }
+        assert_eq!(cache.inner.calls.load(Ordering::Relaxed), 3);
+
+        // The entries survive the transport, but not a change of configuration.
+        let cache =
+            CachingTransport::new(Increment::default(), &dir, &configuration, &cacheable).unwrap();
+        cache.execute_hint("inc", &[Felt252::from(41)]).unwrap();
+        assert_eq!(cache.inner.calls.load(Ordering::Relaxed), 0);
+
+        let mut configuration = configuration;
+        configuration
+            .services
+            .get_mut("Oracle")
+            .unwrap()
+            .methods
+            .remove("next");
+        let cache =
+            CachingTransport::new(Increment::default(), &dir, &configuration, &cacheable).unwrap();
+        cache.execute_hint("inc", &[Felt252::from(41)]).unwrap();
+        assert_eq!(cache.inner.calls.load(Ordering::Relaxed), 1);
+
+        // Another configuration with the same hash doesn't reuse the entries.
+        fs::write(cache.dir.join(CONFIGURATION_FILE), "{}").unwrap();
+        assert!(CachingTransport::new(
+            Increment::default(),
+            dir.path(),
+            &configuration,
+            &cacheable
+        )
+        .is_err());
+
+        let unknown = ["Oracle.dec".to_string()];
+        assert!(
+            CachingTransport::new(Increment::default(), &dir, &configuration, &unknown).is_err()
+        );
+    }
+
+    #[test]
+    fn it_doesnt_cache_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let cacheable = ["Oracle.get".to_string()];
+
+        let cache = CachingTransport::new(
+            Increment::default(),
+            dir.path(),
+            &configuration(),
+            &cacheable,
+        )
+        .unwrap();
+        // `Increment` answers 0 with 1, the encoding of `Err`, and 1 with 2.
+        for _ in 0..2 {
+            cache.execute_hint("get", &[Felt252::ZERO]).unwrap();
+            cache.execute_hint("get", &[Felt252::ONE]).unwrap();
+        }
+        assert_eq!(cache.inner.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn it_stores_entries_missed_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let cacheable = ["Oracle.inc".to_string()];
+        let inner = Synchronized {
+            barrier: Barrier::new(2),
+        };
+
+        let cache = CachingTransport::new(inner, &dir, &configuration(), &cacheable).unwrap();
+        thread::scope(|scope| {
+            let calls =
+                [(); 2].map(|_| scope.spawn(|| cache.execute_hint("inc", &[Felt252::from(41)])));
+            for call in calls {
+                assert_eq!(call.join().unwrap().unwrap(), vec![Felt252::from(42)]);
+            }
+        });
+
+        let cache = CachingTransport::new(Increment::default(), &dir, &configuration(), &cacheable)
+            .unwrap();
+        cache.execute_hint("inc", &[Felt252::from(41)]).unwrap();
+        assert_eq!(cache.inner.calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn it_hashes_with_fnv1a() {
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
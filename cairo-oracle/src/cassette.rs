@@ -0,0 +1,279 @@
+//! Transports recording the hints answered by another transport to a cassette, and answering
+//! hints from a cassette.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{from_hex, to_hex, OracleTransport};
+
+/// Line of a cassette, with the felts as hexadecimal strings.
+#[derive(Serialize, Deserialize)]
+struct Interaction {
+    selector: String,
+    input: Vec<String>,
+    output: Vec<String>,
+}
+
+/// Writes `{"selector": <selector>, "input": [<felt>, ...], "output": [<felt>, ...]}` lines to
+/// a cassette for each hint answered by the inner transport.
+pub struct RecordingTransport<T> {
+    inner: T,
+    cassette: Mutex<BufWriter<File>>,
+    path: PathBuf,
+}
+
+impl<T> RecordingTransport<T> {
+    /// Records the hints answered by `inner` to the cassette at `path`, replacing it if it
+    /// exists.
+    pub fn create(inner: T, path: impl Into<PathBuf>) -> Result<Self, Box<str>> {
+        let path = path.into();
+        let cassette = File::create(&path)
+            .map_err(|err| format!("Couldn't create oracle cassette {}: {err}", path.display()))?;
+        Ok(Self {
+            inner,
+            cassette: Mutex::new(BufWriter::new(cassette)),
+            path,
+        })
+    }
+}
+
+impl<T: OracleTransport> OracleTransport for RecordingTransport<T> {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let output = self.inner.execute_hint(selector, data)?;
+
+        let mut line = serde_json::to_string(&Interaction {
+            selector: selector.to_string(),
+            input: to_hex(data),
+            output: to_hex(&output),
+        })
+        .expect("interactions can be serialized");
+        line.push('\n');
+        // Every line is flushed so that the hints are recorded even if the run fails.
+        let mut cassette = self.cassette.lock().unwrap_or_else(|err| err.into_inner());
+        cassette
+            .write_all(line.as_bytes())
+            .and_then(|_| cassette.flush())
+            .map_err(|err| {
+                format!(
+                    "Couldn't write to oracle cassette {}: {err}",
+                    self.path.display()
+                )
+            })?;
+
+        Ok(output)
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        self.inner.finish()
+    }
+}
+
+/// Answers hints with the outputs recorded in a cassette by `RecordingTransport`, without any
+/// oracle.
+///
+/// Each call must match the next recorded interaction, with the same selector and input, so
+/// hints missing from the cassette, called in another order or more times than recorded, fail.
+/// `finish` fails if some interactions were not replayed.
+pub struct ReplayTransport {
+    interactions: Mutex<VecDeque<Recorded>>,
+    ordered: bool,
+    path: PathBuf,
+}
+
+/// Interaction of a cassette not replayed yet.
+struct Recorded {
+    line: usize,
+    selector: String,
+    input: Vec<Felt252>,
+    output: Vec<Felt252>,
+}
+
+impl ReplayTransport {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<str>> {
+        let path = path.as_ref();
+        let cassette = File::open(path)
+            .map_err(|err| format!("Couldn't open oracle cassette {}: {err}", path.display()))?;
+
+        let mut interactions = VecDeque::new();
+        for (number, line) in BufReader::new(cassette).lines().enumerate() {
+            let line = line.map_err(|err| {
+                format!("Couldn't read oracle cassette {}: {err}", path.display())
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = || {
+                format!(
+                    "Invalid interaction on line {} of oracle cassette {}",
+                    number + 1,
+                    path.display()
+                )
+            };
+            let interaction = serde_json::from_str::<Interaction>(&line).map_err(|_| invalid())?;
+            interactions.push_back(Recorded {
+                line: number + 1,
+                selector: interaction.selector,
+                input: from_hex(&interaction.input).ok_or_else(invalid)?,
+                output: from_hex(&interaction.output).ok_or_else(invalid)?,
+            });
+        }
+
+        Ok(Self {
+            interactions: Mutex::new(interactions),
+            ordered: true,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Matches each call with the first interaction not replayed yet with the same selector and
+    /// input, wherever it is in the cassette, for the runs whose hints are called in varying
+    /// orders, e.g. by tests run in parallel.
+    pub fn unordered(mut self) -> Self {
+        self.ordered = false;
+        self
+    }
+}
+
+impl OracleTransport for ReplayTransport {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let mut interactions = self
+            .interactions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let matches = |recorded: &Recorded| recorded.selector == selector && recorded.input == data;
+        let position = if self.ordered {
+            match interactions.front() {
+                Some(next) if !matches(next) => {
+                    return Err(format!(
+                        "Unexpected call to {selector} with input {:?}, expected a call to {} with input {:?} recorded on line {} of oracle cassette {}",
+                        to_hex(data),
+                        next.selector,
+                        to_hex(&next.input),
+                        next.line,
+                        self.path.display()
+                    )
+                    .into())
+                }
+                next => next.map(|_| 0),
+            }
+        } else {
+            interactions.iter().position(matches)
+        };
+
+        position
+            .and_then(|position| interactions.remove(position))
+            .map(|recorded| recorded.output)
+            .ok_or_else(|| {
+                format!(
+                    "Unexpected call to {selector} with input {:?}, not recorded in oracle cassette {}",
+                    to_hex(data),
+                    self.path.display()
+                )
+                .into()
+            })
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        let interactions = self
+            .interactions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if interactions.is_empty() {
+            return Ok(());
+        }
+
+        let unreplayed = interactions
+            .iter()
+            .map(|recorded| {
+                format!(
+                    "line {}: {} with input {:?}",
+                    recorded.line,
+                    recorded.selector,
+                    to_hex(&recorded.input)
+                )
+            })
+            .join("\n");
+        Err(format!(
+            "{} in {}:\n{unreplayed}",
+            interactions.len(),
+            self.path.display()
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Increment;
+
+    /// Cassette of the calls to `inc` with the inputs `ns`.
+    fn record(ns: &[u64]) -> tempfile::NamedTempFile {
+        let cassette = tempfile::NamedTempFile::new().unwrap();
+        let recording = RecordingTransport::create(Increment::default(), cassette.path()).unwrap();
+        for n in ns {
+            recording.execute_hint("inc", &[Felt252::from(*n)]).unwrap();
+        }
+        cassette
+    }
+
+    #[test]
+    fn it_replays_recorded_hints() {
+        let cassette = record(&[41, 41, 15]);
+
+        let replay = ReplayTransport::open(cassette.path()).unwrap();
+        for _ in 0..2 {
+            let output = replay.execute_hint("inc", &[Felt252::from(41)]).unwrap();
+            assert_eq!(output, vec![Felt252::from(42)]);
+        }
+        assert!(replay.execute_hint("inc", &[Felt252::from(41)]).is_err());
+        assert!(replay.execute_hint("dec", &[Felt252::from(15)]).is_err());
+        assert_eq!(
+            replay.execute_hint("inc", &[Felt252::from(15)]).unwrap(),
+            vec![Felt252::from(16)]
+        );
+        assert!(replay.finish().is_ok());
+        assert!(replay.execute_hint("inc", &[Felt252::from(15)]).is_err());
+    }
+
+    #[test]
+    fn it_rejects_calls_in_another_order() {
+        let cassette = record(&[1, 2]);
+
+        let replay = ReplayTransport::open(cassette.path()).unwrap();
+        let err = replay.execute_hint("inc", &[Felt252::from(2)]).unwrap_err();
+        assert!(
+            err.contains("expected a call to inc with input [\"0x1\"] recorded on line 1"),
+            "{err}"
+        );
+
+        let replay = ReplayTransport::open(cassette.path()).unwrap().unordered();
+        for n in [2, 1] {
+            let output = replay.execute_hint("inc", &[Felt252::from(n)]).unwrap();
+            assert_eq!(output, vec![Felt252::from(n + 1)]);
+        }
+        assert!(replay.finish().is_ok());
+    }
+
+    #[test]
+    fn it_reports_interactions_not_replayed() {
+        let cassette = record(&[1, 2, 3]);
+
+        let replay = ReplayTransport::open(cassette.path()).unwrap();
+        replay.execute_hint("inc", &[Felt252::from(1)]).unwrap();
+        let err = replay.finish().unwrap_err();
+        assert!(
+            err.starts_with(&format!("2 in {}:", cassette.path().display())),
+            "{err}"
+        );
+        assert!(err.contains("line 2: inc with input [\"0x2\"]"), "{err}");
+        assert!(err.contains("line 3: inc with input [\"0x3\"]"), "{err}");
+    }
+}
@@ -0,0 +1,200 @@
+//! HTTP client shared by the transports calling oracle servers.
+
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::tls::{Certificate, Identity};
+use reqwest::Url;
+
+/// Maximum duration of an oracle call by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Options of the connections to oracle servers.
+#[derive(Clone, Debug)]
+pub struct OracleOptions {
+    /// Maximum duration of each attempt of an oracle call.
+    pub timeout: Duration,
+    pub retry_policy: RetryPolicy,
+    /// Headers sent with each call, e.g. `("Authorization", "Bearer <token>")`.
+    pub headers: Vec<(String, String)>,
+    /// PEM file of the certificate authorities trusted in addition to the system ones.
+    pub ca_certificate: Option<PathBuf>,
+    /// PEM file of the certificate presented to the server for mutual TLS.
+    pub client_certificate: Option<PathBuf>,
+    /// PEM file of the PKCS #8 private key of `client_certificate`.
+    pub client_key: Option<PathBuf>,
+}
+
+impl Default for OracleOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            headers: Vec::new(),
+            ca_certificate: None,
+            client_certificate: None,
+            client_key: None,
+        }
+    }
+}
+
+/// Retries of the oracle calls failing to connect, timing out or answered with a retryable
+/// status, after an exponential backoff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt, none by default.
+    pub retries: u32,
+    /// Delay before the first retry, doubled before each of the following ones.
+    pub backoff: Duration,
+    /// HTTP status codes of the responses to retry.
+    pub retry_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            retry_statuses: vec![429, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following the attempt number `attempt`, starting from 0.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// Connection pool to oracle servers, applying the timeout and retry policy of the
+/// `OracleOptions` it is created with. Clones share the same pool.
+#[derive(Clone, Debug)]
+pub struct OracleClient {
+    client: Client,
+    retry_policy: RetryPolicy,
+}
+
+impl Default for OracleClient {
+    fn default() -> Self {
+        Self {
+            client: Client::new(),
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+impl OracleClient {
+    pub fn new(options: &OracleOptions) -> Result<Self, Box<str>> {
+        Self::build(Client::builder(), options)
+    }
+
+    fn build(mut builder: ClientBuilder, options: &OracleOptions) -> Result<Self, Box<str>> {
+        builder = builder.default_headers(header_map(&options.headers)?);
+
+        if let Some(ca_certificate) = &options.ca_certificate {
+            let certificates = Certificate::from_pem_bundle(&read(ca_certificate)?)
+                .map_err(|err| invalid_file(ca_certificate, err))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (&options.client_certificate, &options.client_key) {
+            (Some(certificate), Some(key)) => {
+                let identity = Identity::from_pkcs8_pem(&read(certificate)?, &read(key)?)
+                    .map_err(|err| invalid_file(certificate, err))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Box::from(
+                    "The client certificate and key of the oracle must be given together",
+                ))
+            }
+        }
+
+        let client = builder
+            .timeout(options.timeout)
+            .build()
+            .map_err(|err| format!("Couldn't create oracle client: {err}"))?;
+        Ok(Self {
+            client,
+            retry_policy: options.retry_policy.clone(),
+        })
+    }
+
+    /// POSTs the request built by `request` to `url`, and builds it again for each retry.
+    pub(crate) fn post(
+        &self,
+        url: &Url,
+        request: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response, Box<str>> {
+        let mut attempt = 0;
+        loop {
+            let response = request(self.client.post(url.clone())).send();
+            let retryable = match &response {
+                Ok(response) => self
+                    .retry_policy
+                    .retry_statuses
+                    .contains(&response.status().as_u16()),
+                Err(err) => err.is_connect() || err.is_timeout(),
+            };
+            if !retryable || attempt >= self.retry_policy.retries {
+                return response.map_err(|err| {
+                    if err.is_timeout() {
+                        format!("Oracle server {url} didn't respond in time.").into()
+                    } else {
+                        format!("Couldn't connect to oracle server {url}. Is the server running?")
+                            .into()
+                    }
+                });
+            }
+
+            thread::sleep(self.retry_policy.delay(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+/// Validated `headers` of oracle calls, marked as sensitive.
+pub(crate) fn header_map(headers: &[(String, String)]) -> Result<HeaderMap, Box<str>> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::try_from(name)
+            .map_err(|err| format!("Invalid oracle header name {name:?}: {err}"))?;
+        let mut value = HeaderValue::try_from(value)
+            .map_err(|err| format!("Invalid value of oracle header {name}: {err}"))?;
+        value.set_sensitive(true);
+        header_map.append(name, value);
+    }
+    Ok(header_map)
+}
+
+pub(crate) fn read(path: &Path) -> Result<Vec<u8>, Box<str>> {
+    std::fs::read(path).map_err(|err| format!("Couldn't read {}: {err}", path.display()).into())
+}
+
+fn invalid_file(path: &Path, err: reqwest::Error) -> Box<str> {
+    format!("Invalid certificate or key {}: {err}", path.display()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_doubles_the_backoff() {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(100),
+            ..Default::default()
+        };
+        assert_eq!(retry_policy.delay(0), Duration::from_millis(100));
+        assert_eq!(retry_policy.delay(2), Duration::from_millis(400));
+        assert!(retry_policy.delay(u32::MAX) > Duration::from_secs(3600));
+    }
+}
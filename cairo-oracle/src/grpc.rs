@@ -0,0 +1,318 @@
+//! Transport calling the methods of a gRPC server.
+
+use std::thread;
+
+use bytes::{Buf, BufMut};
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::{decode_protobuf, encode_protobuf};
+use reqwest::Url;
+use starknet_types_core::felt::Felt as Felt252;
+use tokio::runtime::Runtime;
+use tonic::client::Grpc;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Code, Request, Status};
+
+use crate::client::read;
+use crate::{
+    error_answer, method_declaration, ok_answer, OracleOptions, OracleTransport, RetryPolicy,
+};
+
+/// Calls the unary gRPC method `{server_url}/package.Service/Method` with the request as a
+/// Protobuf message, over HTTP/2 without TLS for `http` URLs.
+///
+/// Fallible methods answer the calls failing with a gRPC status with `Err(OracleError)`, unless
+/// the server couldn't be reached or didn't respond in time.
+pub struct GrpcTransport {
+    server_url: Url,
+    runtime: Runtime,
+    channel: Channel,
+    metadata: MetadataMap,
+    retry_policy: RetryPolicy,
+    configuration: Configuration,
+}
+
+impl GrpcTransport {
+    pub fn new(server_url: Url, configuration: Configuration) -> Result<Self, Box<str>> {
+        Self::with_options(server_url, configuration, &Default::default())
+    }
+
+    /// Connects to the server with the timeout, retry policy, headers and TLS options of
+    /// `options`. Retries apply to the calls failing with the `UNAVAILABLE` status, which
+    /// includes the HTTP statuses 429, 502, 503 and 504, or timing out.
+    pub fn with_options(
+        server_url: Url,
+        configuration: Configuration,
+        options: &OracleOptions,
+    ) -> Result<Self, Box<str>> {
+        let mut endpoint = Endpoint::from_shared(server_url.to_string())
+            .map_err(|err| format!("oracle-server must be a valid URL, got {server_url}: {err}"))?
+            .connect_timeout(options.timeout)
+            .timeout(options.timeout);
+
+        if server_url.scheme() == "https" {
+            let mut tls = ClientTlsConfig::new();
+            if let Some(ca_certificate) = &options.ca_certificate {
+                tls = tls.ca_certificate(Certificate::from_pem(read(ca_certificate)?));
+            }
+            match (&options.client_certificate, &options.client_key) {
+                (Some(certificate), Some(key)) => {
+                    tls = tls.identity(Identity::from_pem(read(certificate)?, read(key)?));
+                }
+                (None, None) => {}
+                _ => {
+                    return Err(Box::from(
+                        "The client certificate and key of the oracle must be given together",
+                    ))
+                }
+            }
+            endpoint = endpoint
+                .tls_config(tls)
+                .map_err(|err| format!("Invalid certificate or key of the oracle: {err}"))?;
+        }
+
+        let mut metadata = MetadataMap::new();
+        for (name, value) in &options.headers {
+            let key = AsciiMetadataKey::from_bytes(name.as_bytes())
+                .map_err(|err| format!("Invalid oracle header name {name:?}: {err}"))?;
+            let value = AsciiMetadataValue::try_from(value.as_str())
+                .map_err(|err| format!("Invalid value of oracle header {name}: {err}"))?;
+            metadata.append(key, value);
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| format!("Couldn't create oracle client: {err}"))?;
+        // The channel connects on the first call, from the runtime it is created in.
+        let channel = {
+            let _runtime = runtime.enter();
+            endpoint.connect_lazy()
+        };
+
+        Ok(Self {
+            server_url,
+            runtime,
+            channel,
+            metadata,
+            retry_policy: options.retry_policy.clone(),
+            configuration,
+        })
+    }
+
+    /// Calls the method at `path` with the request `message`, and retries it according to the
+    /// retry policy. The status is boxed, as it is much larger than the message.
+    fn call(&self, path: PathAndQuery, message: Vec<u8>) -> Result<Vec<u8>, Box<Status>> {
+        let mut attempt = 0;
+        loop {
+            let mut request = Request::new(message.clone());
+            *request.metadata_mut() = self.metadata.clone();
+            let response = self.runtime.block_on(async {
+                let mut grpc = Grpc::new(self.channel.clone());
+                grpc.ready()
+                    .await
+                    .map_err(|err| Status::from_error(Box::new(err)))?;
+                grpc.unary(request, path.clone(), BytesCodec).await
+            });
+
+            match response {
+                Err(status) if is_unanswered(&status) && attempt < self.retry_policy.retries => {
+                    thread::sleep(self.retry_policy.delay(attempt));
+                    attempt += 1;
+                }
+                response => {
+                    return response
+                        .map(|response| response.into_inner())
+                        .map_err(Box::new)
+                }
+            }
+        }
+    }
+}
+
+impl OracleTransport for GrpcTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (_, _, configuration) = method_declaration(&self.configuration, selector)?;
+        let grpc_path = configuration.grpc_path.as_deref().ok_or_else(|| {
+            format!("No gRPC path for {selector} in the oracle configuration, regenerate the .lock file")
+        })?;
+        let path = format!(
+            "{}/{}",
+            self.server_url.path().trim_end_matches('/'),
+            grpc_path.trim_start_matches('/')
+        );
+        let path = PathAndQuery::try_from(path)
+            .map_err(|err| format!("Invalid gRPC path {grpc_path} of {selector}: {err}"))?;
+
+        let data = encode_protobuf(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error encoding input of {selector}: {err}"))?;
+
+        let message = match self.call(path, data) {
+            Ok(message) => message,
+            Err(status) if configuration.fallible && !is_unanswered(&status) => {
+                return Ok(error_answer(
+                    i32::from(status.code()).into(),
+                    status.message(),
+                ));
+            }
+            Err(status) => {
+                return Err(Box::from(format!(
+                    "gRPC call {grpc_path} to oracle server {} failed with status {}: {}",
+                    self.server_url,
+                    i32::from(status.code()),
+                    status.message()
+                )))
+            }
+        };
+
+        let output = decode_protobuf(&self.configuration, &configuration.output, &message)
+            .map_err(|err| format!("Error decoding output of {selector}: {err}"))?;
+
+        Ok(ok_answer(configuration, output))
+    }
+}
+
+/// Whether a call failed without being answered by the server: it couldn't be reached or was
+/// overloaded, or the call timed out.
+fn is_unanswered(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::Cancelled | Code::DeadlineExceeded
+    )
+}
+
+/// Codec passing through the messages, which are encoded by `cairo_proto_serde`.
+#[derive(Clone, Copy)]
+struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Encode = Vec<u8>;
+    type Decode = Vec<u8>;
+    type Encoder = BytesCodec;
+    type Decoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesCodec
+    }
+}
+
+impl Encoder for BytesCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Vec<u8>>, Status> {
+        Ok(Some(src.copy_to_bytes(src.remaining()).to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use h2::server::SendResponse;
+    use h2::RecvStream;
+    use tonic::codegen::http::{self, HeaderMap};
+
+    use super::*;
+    use crate::tests::configuration;
+
+    /// Starts a gRPC server answering the requests with `n` 0 with `n` 42, and failing those
+    /// with `n` 1 with a status in the trailers and the others with a status in the headers.
+    fn serve() -> Url {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        listener.set_nonblocking(true).unwrap();
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                loop {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    tokio::spawn(async move {
+                        let mut connection = h2::server::handshake(socket).await.unwrap();
+                        while let Some(Ok((request, respond))) = connection.accept().await {
+                            tokio::spawn(answer(request, respond));
+                        }
+                    });
+                }
+            });
+        });
+        server_url
+    }
+
+    async fn answer(request: http::Request<RecvStream>, mut respond: SendResponse<Bytes>) {
+        let mut body = request.into_body();
+        let mut request = Vec::new();
+        while let Some(data) = body.data().await {
+            let data = data.unwrap();
+            body.flow_control().release_capacity(data.len()).unwrap();
+            request.extend_from_slice(&data);
+        }
+
+        // The message follows the 5 bytes prefix, and `n` its key if it isn't 0.
+        let n = request.get(6).copied().unwrap_or(0);
+        let mut status = HeaderMap::new();
+        status.insert("grpc-status", "5".parse().unwrap());
+        status.insert("grpc-message", "not%20found".parse().unwrap());
+        let mut response = http::Response::new(());
+        response
+            .headers_mut()
+            .insert("content-type", "application/grpc".parse().unwrap());
+
+        match n {
+            0 => {
+                let mut stream = respond.send_response(response, false).unwrap();
+                stream
+                    .send_data(Bytes::from_static(&[0, 0, 0, 0, 2, 8, 42]), false)
+                    .unwrap();
+                let mut ok = HeaderMap::new();
+                ok.insert("grpc-status", "0".parse().unwrap());
+                stream.send_trailers(ok).unwrap();
+            }
+            1 => {
+                let mut stream = respond.send_response(response, false).unwrap();
+                stream.send_trailers(status).unwrap();
+            }
+            _ => {
+                response.headers_mut().extend(status);
+                respond.send_response(response, true).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn it_calls_grpc_servers() {
+        let oracle = GrpcTransport::new(serve(), configuration()).unwrap();
+        let output = oracle.execute_hint("sqrt", &[Felt252::ZERO]).unwrap();
+        assert_eq!(output, vec![Felt252::from(42)]);
+        let output = oracle.execute_hint("get", &[Felt252::ZERO]).unwrap();
+        assert_eq!(output, vec![Felt252::ZERO, Felt252::from(42)]);
+
+        for n in [1, 2] {
+            let answer = oracle.execute_hint("get", &[Felt252::from(n)]).unwrap();
+            assert_eq!(answer, error_answer(5, "not found"));
+            let err = oracle
+                .execute_hint("sqrt", &[Felt252::from(n)])
+                .unwrap_err();
+            assert!(err.contains("failed with status 5: not found"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Built-in transports sending hints to an oracle server over HTTP.
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::{decode_protobuf, deserialize_cairo_serde, encode_protobuf};
+use indoc::formatdoc;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::Url;
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{
+    json_answer, json_body, method_declaration, method_path, ok_answer, OracleClient,
+    OracleTransport,
+};
+
+const PROTOBUF_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// URL of the endpoint of a method, `{server_url}/{Service}/{method}`, or
+/// `{server_url}/{method}` for unqualified selectors.
+fn method_url(
+    server_url: &Url,
+    selector: &str,
+    service: &str,
+    method: &str,
+) -> Result<Url, Box<str>> {
+    let path = method_path(selector, service, method);
+    let mut url = server_url.clone();
+    url.path_segments_mut()
+        .map_err(|_| format!("oracle-server {server_url} cannot be a base URL"))?
+        .extend(path[1..].split('/'));
+    Ok(url)
+}
+
+/// POSTs the request as JSON to `{server_url}/{Service}/{method}` and expects `{"result": <response>}`
+/// back.
+pub struct HttpJsonTransport {
+    server_url: Url,
+    client: OracleClient,
+    configuration: Configuration,
+}
+
+impl HttpJsonTransport {
+    pub fn new(server_url: Url, configuration: Configuration) -> Self {
+        Self::with_client(server_url, OracleClient::default(), configuration)
+    }
+
+    /// Sends the requests with `client`, reusing its pooled connections.
+    pub fn with_client(
+        server_url: Url,
+        client: OracleClient,
+        configuration: Configuration,
+    ) -> Self {
+        Self {
+            server_url,
+            client,
+            configuration,
+        }
+    }
+}
+
+impl OracleTransport for HttpJsonTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let server_url = method_url(&self.server_url, selector, service, method)?;
+
+        let data = deserialize_cairo_serde(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error deserializing input of {selector}: {err}"))?;
+
+        let req = self
+            .client
+            .post(&server_url, |request| request.json(&data))?;
+
+        let status_code = req.error_for_status_ref().map(|_| ());
+        let body = req.text().map_err(|_| {
+            formatdoc! {
+                r#"
+                Response from oracle server can't be parsed as string."#
+            }
+        })?;
+
+        let body = json_body(
+            &body,
+            status_code.is_ok(),
+            |body| configuration.fallible && body.get("error").is_some(),
+            server_url.as_str(),
+        )?;
+
+        json_answer(&self.configuration, configuration, selector, &body)
+    }
+}
+
+/// POSTs the request to `{server_url}/{Service}/{method}` and receives the response as Protobuf
+/// messages, without the JSON envelope.
+pub struct HttpProtobufTransport {
+    server_url: Url,
+    client: OracleClient,
+    configuration: Configuration,
+}
+
+impl HttpProtobufTransport {
+    pub fn new(server_url: Url, configuration: Configuration) -> Self {
+        Self::with_client(server_url, OracleClient::default(), configuration)
+    }
+
+    /// Sends the requests with `client`, reusing its pooled connections.
+    pub fn with_client(
+        server_url: Url,
+        client: OracleClient,
+        configuration: Configuration,
+    ) -> Self {
+        Self {
+            server_url,
+            client,
+            configuration,
+        }
+    }
+}
+
+impl OracleTransport for HttpProtobufTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let server_url = method_url(&self.server_url, selector, service, method)?;
+
+        let data = encode_protobuf(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error encoding input of {selector}: {err}"))?;
+
+        let req = self.client.post(&server_url, |request| {
+            request
+                .header(CONTENT_TYPE, PROTOBUF_CONTENT_TYPE)
+                .header(ACCEPT, PROTOBUF_CONTENT_TYPE)
+                .body(data.clone())
+        })?;
+
+        let status_code = req.error_for_status_ref().map(|_| ());
+        let body = req.bytes().map_err(|_| {
+            formatdoc! {
+                r#"
+                Response from oracle server can't be read."#
+            }
+        })?;
+
+        status_code.map_err(|_| {
+            let body = String::from_utf8_lossy(&body);
+            formatdoc! {
+                r#"
+                Received {body:?}.
+                Response status from oracle server not successful."#
+            }
+        })?;
+
+        let output = decode_protobuf(&self.configuration, &configuration.output, &body)
+            .map_err(|err| format!("Error decoding output of {selector}: {err}"))?;
+
+        Ok(ok_answer(configuration, output))
+    }
+}
@@ -0,0 +1,163 @@
+//! Transport calling the methods of a JSON-RPC 2.0 server over HTTP.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::{deserialize_cairo_serde, serialize_cairo_serde};
+use indoc::formatdoc;
+use reqwest::Url;
+use serde_json::{json, Value};
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{
+    error_answer, json_body, method_declaration, ok_answer, OracleClient, OracleTransport,
+};
+
+/// POSTs `{"jsonrpc": "2.0", "method": "<Service>.<method>", "params": <request>, "id": <id>}`
+/// to `server_url`, and expects `{"jsonrpc": "2.0", "result": <response>, "id": <id>}` back.
+/// JSON-RPC `error` objects fail the hint.
+pub struct JsonRpcTransport {
+    server_url: Url,
+    client: OracleClient,
+    configuration: Configuration,
+    next_id: AtomicU64,
+}
+
+impl JsonRpcTransport {
+    pub fn new(server_url: Url, configuration: Configuration) -> Self {
+        Self::with_client(server_url, OracleClient::default(), configuration)
+    }
+
+    /// Sends the requests with `client`, reusing its pooled connections.
+    pub fn with_client(
+        server_url: Url,
+        client: OracleClient,
+        configuration: Configuration,
+    ) -> Self {
+        Self {
+            server_url,
+            client,
+            configuration,
+            next_id: AtomicU64::new(1),
+        }
+    }
+}
+
+impl OracleTransport for JsonRpcTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let server_url = &self.server_url;
+
+        let data = deserialize_cairo_serde(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error deserializing input of {selector}: {err}"))?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": format!("{service}.{method}"),
+            "params": data,
+            "id": id,
+        });
+
+        let req = self
+            .client
+            .post(server_url, |builder| builder.json(&request))?;
+
+        let status_code = req.error_for_status_ref().map(|_| ());
+        let body = req.text().map_err(|_| {
+            formatdoc! {
+                r#"
+                Response from oracle server can't be parsed as string."#
+            }
+        })?;
+
+        let response = json_body(
+            &body,
+            status_code.is_ok(),
+            |response| response.get("error").is_some(),
+            server_url.as_str(),
+        )?;
+
+        if configuration.fallible {
+            if let Some((code, message)) = jsonrpc_error(&response) {
+                return Ok(error_answer(code, message));
+            }
+        }
+        let output = jsonrpc_result(&response, id)?;
+
+        let output = serialize_cairo_serde(&self.configuration, &configuration.output, output)
+            .map_err(|err| format!("Error serializing output of {selector}: {err}"))?;
+
+        Ok(ok_answer(configuration, output))
+    }
+}
+
+/// Code and message of the error object of a JSON-RPC response.
+fn jsonrpc_error(response: &Value) -> Option<(i64, &str)> {
+    let error = response.get("error")?;
+    let code = error
+        .get("code")
+        .and_then(Value::as_i64)
+        .unwrap_or_default();
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    Some((code, message))
+}
+
+/// Extracts the result of the request `id` from a JSON-RPC 2.0 response.
+fn jsonrpc_result(response: &Value, id: u64) -> Result<&Value, Box<str>> {
+    if response.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(formatdoc! {r#"
+            Received {response:?}.
+            Expected a JSON-RPC 2.0 response from oracle server.
+        "#}
+        .into());
+    }
+
+    if let Some((code, message)) = jsonrpc_error(response) {
+        return Err(match response["error"].get("data") {
+            Some(data) => {
+                format!("Oracle server returned JSON-RPC error {code}: {message} ({data})")
+            }
+            None => format!("Oracle server returned JSON-RPC error {code}: {message}"),
+        }
+        .into());
+    }
+
+    if response.get("id").and_then(Value::as_u64) != Some(id) {
+        return Err(formatdoc! {r#"
+            Received {response:?}.
+            Expected the response to JSON-RPC request {id} from oracle server.
+        "#}
+        .into());
+    }
+
+    response.get("result").ok_or_else(|| {
+        formatdoc! {r#"
+            Received {response:?}.
+            Expected response format from oracle server is {{"jsonrpc": "2.0", "result": <response_object>, "id": {id}}}.
+        "#}
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_responses_and_errors() {
+        let response = json!({"jsonrpc": "2.0", "result": {"n": 42}, "id": 7});
+        assert_eq!(jsonrpc_result(&response, 7).unwrap(), &json!({"n": 42}));
+        assert!(jsonrpc_result(&response, 8).is_err());
+
+        let response = json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 7});
+        assert_eq!(
+            &*jsonrpc_result(&response, 7).unwrap_err(),
+            "Oracle server returned JSON-RPC error -32601: Method not found"
+        );
+
+        assert!(jsonrpc_result(&json!({"result": {"n": 42}}), 7).is_err());
+    }
+}
@@ -2,122 +2,417 @@ use std::fs::File;
 use std::io::BufReader;
 
 use cairo_proto_serde::configuration::{Configuration, MethodDeclaration};
-use cairo_proto_serde::{deserialize_cairo_serde, serialize_cairo_serde};
+use cairo_proto_serde::{serialize_byte_array, serialize_cairo_serde};
 use indoc::formatdoc;
 use itertools::Itertools;
 use reqwest::Url;
 use serde_json::Value;
 use starknet_types_core::felt::Felt as Felt252;
 
+mod cache;
+mod cassette;
+mod client;
+mod grpc;
+mod http;
+mod jsonrpc;
+mod registry;
+mod router;
+mod subprocess;
+#[cfg(unix)]
+mod unix;
+mod wasm;
+
+pub use cache::CachingTransport;
+pub use cassette::{RecordingTransport, ReplayTransport};
+pub use client::{OracleClient, OracleOptions, RetryPolicy, DEFAULT_TIMEOUT};
+pub use grpc::GrpcTransport;
+pub use http::{HttpJsonTransport, HttpProtobufTransport};
+pub use jsonrpc::JsonRpcTransport;
+pub use registry::{OracleError, OracleRegistry};
+pub use router::OracleRouter;
+pub use subprocess::SubprocessTransport;
+#[cfg(unix)]
+pub use unix::UnixSocketTransport;
+pub use wasm::{WasmEncoding, WasmTransport};
+
+/// Suffix of the oracle server URL scheme selecting the Protobuf binary wire format, e.g.
+/// `http+protobuf://127.0.0.1:3000`.
+const PROTOBUF_SCHEME_SUFFIX: &str = "+protobuf";
+
+/// Suffix of the oracle server URL scheme selecting JSON-RPC 2.0, e.g.
+/// `http+jsonrpc://127.0.0.1:3000/rpc`.
+const JSONRPC_SCHEME_SUFFIX: &str = "+jsonrpc";
+
+/// Backend answering the hints of a Cairo program.
+///
+/// `data` is the Cairo Serde encoding of the request of the method identified by `selector`, and
+/// the Cairo Serde encoding of its response is returned.
+pub trait OracleTransport {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>>;
+
+    /// Checks, once the program has run, that the transport answered all the hints it expected.
+    /// Replayed cassettes fail with the interactions that were not called.
+    fn finish(&self) -> Result<(), Box<str>> {
+        Ok(())
+    }
+}
+
+impl<T: OracleTransport + ?Sized> OracleTransport for &T {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        (**self).execute_hint(selector, data)
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        (**self).finish()
+    }
+}
+
+impl<T: OracleTransport + ?Sized> OracleTransport for Box<T> {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        (**self).execute_hint(selector, data)
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        (**self).finish()
+    }
+}
+
+/// Oracle using the built-in transport selected by the scheme of the server URL.
 pub struct CairoOracle {
-    server: String,
-    configuration: Configuration,
+    transport: Box<dyn OracleTransport + Send + Sync>,
 }
 
 impl CairoOracle {
-    pub fn new(server: String, configuration: Configuration) -> Self {
-        Self { server, configuration }
+    pub fn new(server: &str, configuration: Configuration) -> Result<Self, Box<str>> {
+        Self::with_options(server, configuration, &OracleOptions::default())
     }
 
-    pub fn new_from_env() -> Self {
+    /// Oracle calling `server` with the timeout and retry policy of `options`.
+    pub fn with_options(
+        server: &str,
+        configuration: Configuration,
+        options: &OracleOptions,
+    ) -> Result<Self, Box<str>> {
+        let Some((scheme, rest)) = server.split_once("://") else {
+            return Err(Box::from(format!(
+                "oracle-server must be a valid URL, got {server:?}"
+            )));
+        };
+
+        if scheme == "unix" {
+            return Self::new_unix(rest, configuration, options);
+        }
+
+        if scheme == "grpc" || scheme == "grpcs" {
+            let scheme = if scheme == "grpc" { "http" } else { "https" };
+            let server_url = Url::parse(&format!("{scheme}://{rest}")).map_err(|err| {
+                format!("oracle-server must be a valid URL, got {server:?}: {err}")
+            })?;
+            return Ok(Self {
+                transport: Box::new(GrpcTransport::with_options(
+                    server_url,
+                    configuration,
+                    options,
+                )?),
+            });
+        }
+
+        // `Url` can't change a custom scheme to `http`, so the suffix is dropped beforehand.
+        let (scheme, suffix) = match scheme.find('+') {
+            Some(plus) => scheme.split_at(plus),
+            None => (scheme, ""),
+        };
+        let server_url = Url::parse(&format!("{scheme}://{rest}"))
+            .map_err(|err| format!("oracle-server must be a valid URL, got {server:?}: {err}"))?;
+
+        let transport: Box<dyn OracleTransport + Send + Sync> = match (scheme, suffix) {
+            ("http" | "https", "") => {
+                let client = OracleClient::new(options)?;
+                Box::new(HttpJsonTransport::with_client(server_url, client, configuration))
+            }
+            ("http" | "https", PROTOBUF_SCHEME_SUFFIX) => {
+                let client = OracleClient::new(options)?;
+                Box::new(HttpProtobufTransport::with_client(server_url, client, configuration))
+            }
+            ("http" | "https", JSONRPC_SCHEME_SUFFIX) => {
+                let client = OracleClient::new(options)?;
+                Box::new(JsonRpcTransport::with_client(server_url, client, configuration))
+            }
+            _ => {
+                return Err(Box::from(format!(
+                    "Unsupported oracle-server URL scheme in {server:?}, expected one of http, https, http+protobuf, https+protobuf, http+jsonrpc, https+jsonrpc, grpc, grpcs or unix"
+                )))
+            }
+        };
+        Ok(Self { transport })
+    }
+
+    #[cfg(unix)]
+    fn new_unix(
+        socket: &str,
+        configuration: Configuration,
+        options: &OracleOptions,
+    ) -> Result<Self, Box<str>> {
+        if socket.is_empty() {
+            return Err(Box::from(
+                "oracle-server must be the path of a socket, e.g. unix:///tmp/oracle.sock",
+            ));
+        }
+        Ok(Self {
+            transport: Box::new(
+                UnixSocketTransport::new(socket, configuration).with_options(options)?,
+            ),
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn new_unix(
+        _socket: &str,
+        _configuration: Configuration,
+        _options: &OracleOptions,
+    ) -> Result<Self, Box<str>> {
+        Err(Box::from(
+            "unix:// oracle servers are only supported on Unix",
+        ))
+    }
+
+    pub fn new_from_env() -> Result<Self, Box<str>> {
         // TODO: throw an error if only one present
         let server = std::env::var("CAIRO_ORACLE_SERVER").ok().unwrap_or_default();
         let Some(configuration) = std::env::var("CAIRO_ORACLE_CONFIG").ok() else {
-            return Self { server: server, configuration: Default::default() };
+            return Self::new(&server, Default::default());
         };
 
         let lock_file = File::open(configuration).expect("CAIRO_ORACLE_CONFIG exists");
         let reader = BufReader::new(lock_file);
         let configuration = serde_json::from_reader(reader).expect("CAIRO_ORACLE_CONFIG is a JSON file with oracle configuration");
-    
-        return Self { server, configuration };
-    }
 
-    fn method_declaration(&self, selector: &str) -> Option<&MethodDeclaration> {
-        self
-            .configuration
-            .services
-            .iter()
-            .find_map(|(_, methods)| methods.methods.get(selector))
+        Self::new(&server, configuration)
     }
+}
 
-    pub fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
-        let Some(configuration) = self.method_declaration(selector) else {
-            return Err(Box::from(format!(
-                "Unknown cheatcode selector: {selector}"
-            )));
-        };
-
-        let mut server_url = Url::parse(&self.server).expect("oracle-server must be a valid URL");
-        server_url.path_segments_mut().expect("cannot be a base URL").push(selector);
+impl OracleTransport for CairoOracle {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        self.transport.execute_hint(selector, data)
+    }
 
-        let data = deserialize_cairo_serde(
-            &self.configuration,
-            &configuration.input,
-            &mut data,
-        );
-        println!("let the oracle decide... Inputs: {data:?}");
+    fn finish(&self) -> Result<(), Box<str>> {
+        self.transport.finish()
+    }
+}
 
-        let client = reqwest::blocking::Client::new();
+/// Felts as hexadecimal strings, as stored in the cassettes and the cache.
+fn to_hex(felts: &[Felt252]) -> Vec<String> {
+    felts.iter().map(|felt| format!("{felt:#x}")).collect()
+}
 
-        let req = client.post(server_url.clone()).json(&data).send().expect(
-            format!("Couldn't connect to oracle server {server_url}. Is the server running?")
-                .as_str(),
-        );
+fn from_hex(felts: &[String]) -> Option<Vec<Felt252>> {
+    felts
+        .iter()
+        .map(|felt| Felt252::from_hex(felt).ok())
+        .collect()
+}
 
-        let status_code = req.error_for_status_ref().map(|_| ());
-        let body = req.text().expect(
-            formatdoc! {
-                r#"
-                Response from oracle server can't be parsed as string."#
-            }
-            .as_str(),
-        );
+/// Service, method and declaration of the method called by the cheatcode `selector`.
+fn method_declaration<'a>(
+    configuration: &'a Configuration,
+    selector: &str,
+) -> Result<(&'a str, &'a str, &'a MethodDeclaration), Box<str>> {
+    configuration.method(selector).ok_or_else(|| {
+        if selector.contains('/') || selector.starts_with('#') {
+            format!("Unknown cheatcode selector: {selector}").into()
+        } else {
+            format!("Unknown cheatcode selector: {selector}, or declared by several services; regenerate the Cairo code to qualify it with its service").into()
+        }
+    })
+}
 
-        status_code.expect(
-            formatdoc! {
-                r#"
-                Received {body:?}.
-                Response status from oracle server not successful."#
-            }
-            .as_str(),
-        );
+/// Path of the endpoint of a method on oracle servers, `/{Service}/{method}`, or `/{method}`
+/// for the unqualified selectors generated by earlier versions.
+fn method_path(selector: &str, service: &str, method: &str) -> String {
+    if selector == method {
+        format!("/{method}")
+    } else {
+        format!("/{service}/{method}")
+    }
+}
 
-        let body = serde_json::from_str::<Value>(body.as_str()).expect(
-            formatdoc! {
-                r#"
-                Received {body:?}.
-                Error converting response from oracle server {server_url} to JSON."#
-            }
-            .as_str(),
-        );
+/// Cairo Serde encoding of the answer to a hint: the response, or `Ok(response)` for fallible
+/// methods.
+fn ok_answer(declaration: &MethodDeclaration, mut output: Vec<Felt252>) -> Vec<Felt252> {
+    if declaration.fallible {
+        output.insert(0, Felt252::ZERO);
+    }
+    output
+}
 
-        let body = body.as_object().expect(
-            formatdoc! {r#"
-                Received {body:?}.
-                Error serialising response as object from oracle server.
-            "#}
-            .as_str(),
-        );
+/// Cairo Serde encoding of `Err(OracleError { code, message })`, the answer of a fallible
+/// method to the errors of the oracle.
+fn error_answer(code: i64, message: &str) -> Vec<Felt252> {
+    let mut output = vec![Felt252::ONE, Felt252::from(code)];
+    output.extend(serialize_byte_array(message.as_bytes()));
+    output
+}
 
-        body.keys()
-            .exactly_one()
-            .map_err(|_| {
-                formatdoc! {r#"
+/// Answer to a hint from the `{"result": <response>}` envelope of a JSON oracle or, for
+/// fallible methods, the `{"error": {"code": <code>, "message": <message>}}` one.
+fn json_answer(
+    configuration: &Configuration,
+    declaration: &MethodDeclaration,
+    selector: &str,
+    body: &Value,
+) -> Result<Vec<Felt252>, Box<str>> {
+    if declaration.fallible {
+        if let Some(error) = body.get("error") {
+            let code = error.get("code").and_then(Value::as_i64);
+            let message = error.get("message").and_then(Value::as_str);
+            let (Some(code), Some(message)) = (code, message) else {
+                return Err(formatdoc! {r#"
                     Received {body:?}.
-                    Expected response format from oracle server is {{"result": <response_object>}}.
+                    Expected error format from oracle server is {{"error": {{"code": <integer>, "message": <string>}}}}.
                 "#}
-            })
-            .unwrap();
+                .into());
+            };
+            return Ok(error_answer(code, message));
+        }
+    }
+
+    let output = json_result(body)?;
+    let output = serialize_cairo_serde(configuration, &declaration.output, output)
+        .map_err(|err| format!("Error serializing output of {selector}: {err}"))?;
+    Ok(ok_answer(declaration, output))
+}
 
-        let output = body.get("result").expect(
+/// Parses the JSON `body` of a response from an oracle server, answered with a `successful`
+/// status or not. Errors may be answered with any status, so an unsuccessful one is only
+/// reported when `is_error` doesn't recognize the body as an error.
+fn json_body(
+    body: &str,
+    successful: bool,
+    is_error: impl FnOnce(&Value) -> bool,
+    server: &str,
+) -> Result<Value, Box<str>> {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) if successful || is_error(&value) => Ok(value),
+        _ if !successful => Err(formatdoc! {
+            r#"
+            Received {body:?}.
+            Response status from oracle server not successful."#
+        }
+        .into()),
+        _ => Err(formatdoc! {
+            r#"
+            Received {body:?}.
+            Error converting response from oracle server {server} to JSON."#
+        }
+        .into()),
+    }
+}
+
+/// Extracts the response from the `{"result": <response>}` envelope of a JSON oracle.
+fn json_result(body: &Value) -> Result<&Value, Box<str>> {
+    let body = body.as_object().ok_or_else(|| {
+        formatdoc! {r#"
+            Received {body:?}.
+            Error serialising response as object from oracle server.
+        "#}
+    })?;
+
+    let output = body
+        .iter()
+        .exactly_one()
+        .ok()
+        .and_then(|(key, value)| (key == "result").then_some(value))
+        .ok_or_else(|| {
             formatdoc! {r#"
                 Received {body:?}.
                 Expected response format from oracle server is {{"result": <response_object>}}.
             "#}
-            .as_str(),
-        );
+        })?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Answers with its input plus one, counting its calls.
+    #[derive(Default)]
+    pub(crate) struct Increment {
+        pub(crate) calls: AtomicU32,
+    }
+
+    impl OracleTransport for Increment {
+        fn execute_hint(
+            &self,
+            _selector: &str,
+            data: &[Felt252],
+        ) -> Result<Vec<Felt252>, Box<str>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![data[0] + Felt252::ONE])
+        }
+    }
+
+    /// Configuration of the transport tests: `SqrtOracle.sqrt` and the fallible `Oracle.get`
+    /// take and return messages, and the other methods a `u64`.
+    pub(crate) fn configuration() -> Configuration {
+        serde_json::from_str(
+            r#"{
+                "enums": {},
+                "messages": {
+                    "oracle::Request": [{"name": "n", "ty": {"primitive": "u64"}, "tag": {"number": 1}}],
+                    "oracle::Response": [{"name": "n", "ty": {"primitive": "u64"}, "tag": {"number": 1}}]
+                },
+                "services": {
+                    "SqrtOracle": {
+                        "sqrt": {"input": {"message": "oracle::Request"}, "output": {"message": "oracle::Response"}, "grpc_path": "/oracle.SqrtOracle/Sqrt"}
+                    },
+                    "Oracle": {
+                        "inc": {"input": {"primitive": "u64"}, "output": {"primitive": "u64"}},
+                        "next": {"input": {"primitive": "u64"}, "output": {"primitive": "u64"}},
+                        "get": {"input": {"message": "oracle::Request"}, "output": {"message": "oracle::Response"}, "fallible": true, "grpc_path": "/oracle.Oracle/Get"}
+                    },
+                    "PriceFeed": {
+                        "price": {"input": {"primitive": "u64"}, "output": {"primitive": "u64"}}
+                    }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_answers_errors_of_fallible_methods() {
+        let configuration = configuration();
+        let (_, _, declaration) = method_declaration(&configuration, "Oracle/get").unwrap();
+
+        let answer = json_answer(&configuration, declaration, "get", &json!({"result": {"n": 42}}));
+        assert_eq!(answer.unwrap(), vec![Felt252::ZERO, Felt252::from(42)]);
+
+        let error = json!({"error": {"code": -1, "message": "no answer"}});
+        let answer = json_answer(&configuration, declaration, "get", &error).unwrap();
+        assert_eq!(answer, error_answer(-1, "no answer"));
+        assert_eq!(answer[..2], [Felt252::ONE, Felt252::from(-1)]);
+
+        let error = json!({"error": "no answer"});
+        assert!(json_answer(&configuration, declaration, "get", &error).is_err());
+    }
+
+    #[test]
+    fn it_accepts_errors_with_any_status() {
+        let is_error = |body: &Value| body.get("error").is_some();
+        let error = r#"{"error": {"code": -1, "message": "no answer"}}"#;
+        assert!(json_body(error, false, is_error, "test").is_ok());
+        assert!(json_body(r#"{"result": 42}"#, true, is_error, "test").is_ok());
 
-        Ok(serialize_cairo_serde(&self.configuration, &configuration.output, output))
+        let err = json_body(r#"{"result": 42}"#, false, is_error, "test").unwrap_err();
+        assert!(err.contains("not successful"), "{err}");
+        let err = json_body("{", true, is_error, "test").unwrap_err();
+        assert!(err.contains("to JSON"), "{err}");
     }
 }
@@ -0,0 +1,190 @@
+//! Transport answering hints in-process with Rust handlers.
+
+use std::collections::HashMap;
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::{decode_protobuf, encode_protobuf};
+use prost::Message;
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{error_answer, method_declaration, ok_answer, OracleTransport};
+
+type Handler = Box<dyn Fn(&[u8]) -> Result<Result<Vec<u8>, OracleError>, Box<str>> + Send + Sync>;
+
+/// Error returned by a handler, answered as `Err(OracleError { code, message })` to fallible
+/// methods.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OracleError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl OracleError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Handlers of the methods declared in `Configuration::services`, taking and returning the
+/// types generated by `prost` from the `.proto` file.
+///
+/// ```ignore
+/// let mut registry = OracleRegistry::new(configuration);
+/// registry.register("SqrtOracle", "sqrt", |request: Request| {
+///     Ok(Response {
+///         n: (request.n as f64).sqrt() as u64,
+///     })
+/// })?;
+/// run_1(Some(registry), ...);
+/// ```
+pub struct OracleRegistry {
+    configuration: Configuration,
+    handlers: HashMap<(String, String), Handler>,
+}
+
+impl OracleRegistry {
+    pub fn new(configuration: Configuration) -> Self {
+        Self {
+            configuration,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Registers the handler of `method` of `service`, replacing any previous one. Only the
+    /// handlers of fallible methods may return an `OracleError`, other errors fail the hint.
+    pub fn register<Req, Res, F>(
+        &mut self,
+        service: &str,
+        method: &str,
+        handler: F,
+    ) -> Result<&mut Self, Box<str>>
+    where
+        Req: Message + Default,
+        Res: Message,
+        F: Fn(Req) -> Result<Res, OracleError> + Send + Sync + 'static,
+    {
+        let declared = self
+            .configuration
+            .services
+            .get(service)
+            .is_some_and(|methods| methods.methods.contains_key(method));
+        if !declared {
+            return Err(Box::from(format!(
+                "Method {service}/{method} is not declared in the oracle configuration"
+            )));
+        }
+
+        let name = format!("{service}/{method}");
+        let handler = move |request: &[u8]| {
+            let request = Req::decode(request)
+                .map_err(|err| format!("Error decoding input of {name}: {err}"))?;
+            Ok(handler(request).map(|response| response.encode_to_vec()))
+        };
+        self.handlers
+            .insert((service.to_string(), method.to_string()), Box::new(handler));
+        Ok(self)
+    }
+}
+
+impl OracleTransport for OracleRegistry {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let handler = self
+            .handlers
+            .get(&(service.to_string(), method.to_string()))
+            .ok_or_else(|| format!("No handler registered for {service}/{method}"))?;
+
+        let request = encode_protobuf(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error encoding input of {selector}: {err}"))?;
+        let response = match handler(&request)? {
+            Ok(response) => response,
+            Err(error) if configuration.fallible => {
+                return Ok(error_answer(error.code, &error.message))
+            }
+            Err(error) => {
+                return Err(Box::from(format!(
+                    "Handler of {service}/{method} failed with code {}: {}, but the method isn't fallible",
+                    error.code, error.message
+                )))
+            }
+        };
+        let output = decode_protobuf(&self.configuration, &configuration.output, &response)
+            .map_err(|err| format!("Error decoding output of {selector}: {err}"))?;
+
+        Ok(ok_answer(configuration, output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::configuration;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Request {
+        #[prost(uint64, tag = "1")]
+        n: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Response {
+        #[prost(uint64, tag = "1")]
+        n: u64,
+    }
+
+    #[test]
+    fn it_answers_registered_methods() {
+        let mut registry = OracleRegistry::new(configuration());
+        registry
+            .register("SqrtOracle", "sqrt", |request: Request| {
+                Ok(Response {
+                    n: (request.n as f64).sqrt() as u64,
+                })
+            })
+            .unwrap();
+
+        let output = registry
+            .execute_hint("sqrt", &[Felt252::from(1764)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(42)]);
+    }
+
+    #[test]
+    fn it_answers_errors_of_fallible_methods() {
+        let mut registry = OracleRegistry::new(configuration());
+        let handler = |request: Request| match request.n {
+            0 => Err(OracleError::new(5, "not found")),
+            n => Ok(Response { n }),
+        };
+        registry.register("Oracle", "get", handler).unwrap();
+        registry.register("SqrtOracle", "sqrt", handler).unwrap();
+
+        let output = registry
+            .execute_hint("Oracle/get", &[Felt252::ONE])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::ZERO, Felt252::ONE]);
+        let output = registry
+            .execute_hint("Oracle/get", &[Felt252::ZERO])
+            .unwrap();
+        assert_eq!(output, error_answer(5, "not found"));
+
+        let err = registry.execute_hint("sqrt", &[Felt252::ZERO]).unwrap_err();
+        assert!(err.contains("isn't fallible"), "{err}");
+    }
+
+    #[test]
+    fn it_rejects_undeclared_and_unregistered_methods() {
+        let mut registry = OracleRegistry::new(configuration());
+        assert!(registry
+            .register("SqrtOracle", "cbrt", |request: Request| Ok(Response {
+                n: request.n
+            }))
+            .is_err());
+        assert!(registry
+            .execute_hint("sqrt", &[Felt252::from(1764)])
+            .is_err());
+    }
+}
@@ -0,0 +1,113 @@
+//! Transport dispatching the hints of each service to its own transport.
+
+use std::collections::HashMap;
+
+use cairo_proto_serde::configuration::Configuration;
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{method_declaration, OracleTransport};
+
+type Transport = Box<dyn OracleTransport + Send + Sync>;
+
+/// Routes the hints of the methods declared in `Configuration::services` to the transport of
+/// their service, e.g. to separate oracle servers, and those of the other services to the
+/// default transport.
+///
+/// ```ignore
+/// let mut router = OracleRouter::new(configuration.clone(), Some(Box::new(default)));
+/// router.route("PriceFeed", CairoOracle::new("http://127.0.0.1:3001", configuration)?)?;
+/// run_1(Some(router), ...);
+/// ```
+pub struct OracleRouter {
+    configuration: Configuration,
+    transports: HashMap<String, Transport>,
+    default: Option<Transport>,
+}
+
+impl OracleRouter {
+    pub fn new(configuration: Configuration, default: Option<Transport>) -> Self {
+        Self {
+            configuration,
+            transports: HashMap::new(),
+            default,
+        }
+    }
+
+    /// Routes the hints of `service` to `transport`, replacing any previous one.
+    pub fn route(
+        &mut self,
+        service: &str,
+        transport: impl OracleTransport + Send + Sync + 'static,
+    ) -> Result<&mut Self, Box<str>> {
+        if !self.configuration.services.contains_key(service) {
+            return Err(Box::from(format!(
+                "Service {service} is not declared in the oracle configuration"
+            )));
+        }
+
+        self.transports
+            .insert(service.to_string(), Box::new(transport));
+        Ok(self)
+    }
+}
+
+impl OracleTransport for OracleRouter {
+    fn execute_hint(&self, selector: &str, data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, _, _) = method_declaration(&self.configuration, selector)?;
+        let transport = self
+            .transports
+            .get(service)
+            .or(self.default.as_ref())
+            .ok_or_else(|| format!("No oracle for the hints of service {service}"))?;
+        transport.execute_hint(selector, data)
+    }
+
+    fn finish(&self) -> Result<(), Box<str>> {
+        self.transports
+            .values()
+            .chain(&self.default)
+            .try_for_each(|transport| transport.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::configuration;
+
+    /// Answers with a constant.
+    struct Constant(u64);
+
+    impl OracleTransport for Constant {
+        fn execute_hint(
+            &self,
+            _selector: &str,
+            _data: &[Felt252],
+        ) -> Result<Vec<Felt252>, Box<str>> {
+            Ok(vec![Felt252::from(self.0)])
+        }
+    }
+
+    #[test]
+    fn it_routes_hints_by_service() {
+        let configuration = configuration();
+
+        let mut router = OracleRouter::new(configuration.clone(), Some(Box::new(Constant(0))));
+        router
+            .route("SqrtOracle", Constant(1))
+            .unwrap()
+            .route("PriceFeed", Constant(2))
+            .unwrap();
+        assert!(router.route("Clock", Constant(3)).is_err());
+
+        for (selector, answer) in [("sqrt", 1), ("price", 2), ("inc", 0)] {
+            let output = router.execute_hint(selector, &[]).unwrap();
+            assert_eq!(output, vec![Felt252::from(answer)]);
+        }
+        assert!(router.execute_hint("time", &[]).is_err());
+
+        let mut router = OracleRouter::new(configuration, None);
+        router.route("SqrtOracle", Constant(1)).unwrap();
+        assert!(router.execute_hint("inc", &[]).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Transport exchanging newline-delimited JSON messages with a child process.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::deserialize_cairo_serde;
+use indoc::formatdoc;
+use serde_json::{json, Value};
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::{json_answer, method_declaration, OracleTransport, DEFAULT_TIMEOUT};
+
+/// Time given to the child process to exit once its standard input is closed, before it is
+/// killed.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Process {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    /// Lines of the standard output, read by a thread so that reads can time out.
+    lines: Receiver<io::Result<String>>,
+}
+
+/// Writes `{"service": <Service>, "selector": <method>, "input": <request>}` lines to the
+/// standard input of a child process, and reads `{"result": <response>}` lines back from its
+/// standard output.
+///
+/// The process is spawned once and serves all the hints. Its standard input is closed when the
+/// transport is dropped, and it is killed if it doesn't exit shortly afterwards.
+pub struct SubprocessTransport {
+    process: Mutex<Process>,
+    timeout: Duration,
+    configuration: Configuration,
+}
+
+impl SubprocessTransport {
+    pub fn spawn(mut command: Command, configuration: Configuration) -> Result<Self, Box<str>> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Couldn't start oracle command {command:?}: {err}"))?;
+        let stdin = child.stdin.take();
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut line = String::new();
+            match stdout.read_line(&mut line) {
+                // Dropping the sender at the end of the output tells that the process exited.
+                Ok(0) => return,
+                Ok(_) => {
+                    if sender.send(Ok(line)).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    let _ = sender.send(Err(err));
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            process: Mutex::new(Process {
+                child,
+                stdin,
+                lines,
+            }),
+            timeout: DEFAULT_TIMEOUT,
+            configuration,
+        })
+    }
+
+    /// Spawns a command line such as `python "my oracle.py"`, split into words the way a POSIX
+    /// shell does, in `current_dir`.
+    pub fn spawn_command_line(
+        command_line: &str,
+        current_dir: impl AsRef<Path>,
+        configuration: Configuration,
+    ) -> Result<Self, Box<str>> {
+        let words = shell_words::split(command_line)
+            .map_err(|err| format!("Couldn't parse oracle command {command_line:?}: {err}"))?;
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| Box::<str>::from("oracle command must not be empty"))?;
+        let mut command = Command::new(program);
+        command.args(args).current_dir(current_dir);
+        Self::spawn(command, configuration)
+    }
+
+    /// Fails the calls to which the process doesn't respond within `timeout`, and kills it.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl OracleTransport for SubprocessTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+
+        let data = deserialize_cairo_serde(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error deserializing input of {selector}: {err}"))?;
+        let mut request =
+            json!({ "service": service, "selector": method, "input": data }).to_string();
+        request.push('\n');
+
+        let mut process = self.process.lock().unwrap_or_else(|err| err.into_inner());
+        let stdin = process
+            .stdin
+            .as_mut()
+            .expect("stdin is open until the transport is dropped");
+        stdin
+            .write_all(request.as_bytes())
+            .and_then(|_| stdin.flush())
+            .map_err(|err| format!("Couldn't write to oracle command: {err}"))?;
+
+        let body = match process.lines.recv_timeout(self.timeout) {
+            Ok(line) => line.map_err(|err| format!("Couldn't read from oracle command: {err}"))?,
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Box::from("Oracle command exited before responding"))
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                // A late response would be read as the answer to the next hint.
+                let _ = process.child.kill();
+                return Err(Box::from(format!(
+                    "Oracle command didn't respond within {:?}",
+                    self.timeout
+                )));
+            }
+        };
+        drop(process);
+
+        let body = serde_json::from_str::<Value>(body.trim_end()).map_err(|_| {
+            formatdoc! {
+                r#"
+                Received {body:?}.
+                Error converting response from oracle command to JSON."#
+            }
+        })?;
+
+        json_answer(&self.configuration, configuration, selector, &body)
+    }
+}
+
+impl Drop for SubprocessTransport {
+    fn drop(&mut self) {
+        let process = self
+            .process
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner());
+        // Closing the standard input signals the end of the session.
+        drop(process.stdin.take());
+
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while let Ok(None) = process.child.try_wait() {
+            if Instant::now() >= deadline {
+                let _ = process.child.kill();
+                let _ = process.child.wait();
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tests::configuration;
+
+    #[test]
+    fn it_runs_quoted_command_lines_with_a_timeout() {
+        let command_line = r#"sh -c 'while read line; do echo "{\"result\": 42}"; done'"#;
+        let oracle =
+            SubprocessTransport::spawn_command_line(command_line, ".", configuration()).unwrap();
+        let output = oracle.execute_hint("inc", &[Felt252::from(1)]).unwrap();
+        assert_eq!(output, vec![Felt252::from(42)]);
+
+        let oracle = SubprocessTransport::spawn_command_line("sleep 10", ".", configuration())
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+        let err = oracle.execute_hint("inc", &[Felt252::from(1)]).unwrap_err();
+        assert!(err.contains("didn't respond"), "{err}");
+        // The process is killed rather than answering the next hint late.
+        assert!(oracle.execute_hint("inc", &[Felt252::from(1)]).is_err());
+    }
+}
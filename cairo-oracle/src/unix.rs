@@ -0,0 +1,343 @@
+//! Transport speaking the HTTP/JSON protocol of oracle servers over a Unix domain socket.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::deserialize_cairo_serde;
+use indoc::formatdoc;
+use reqwest::header::HeaderMap;
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::client::header_map;
+use crate::{
+    json_answer, json_body, method_declaration, method_path, OracleOptions, OracleTransport,
+    RetryPolicy, DEFAULT_TIMEOUT,
+};
+
+/// POSTs the request as JSON to `/{Service}/{method}` on a server listening on a Unix domain socket, and
+/// expects `{"result": <response>}` back.
+pub struct UnixSocketTransport {
+    socket: PathBuf,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    headers: HeaderMap,
+    configuration: Configuration,
+}
+
+impl UnixSocketTransport {
+    pub fn new(socket: impl Into<PathBuf>, configuration: Configuration) -> Self {
+        Self {
+            socket: socket.into(),
+            timeout: DEFAULT_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            headers: HeaderMap::new(),
+            configuration,
+        }
+    }
+
+    /// Fails the calls to which the server doesn't respond in full within `timeout`, however
+    /// slowly it sends the response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Calls the server with the timeout, retry policy and headers of `options`. Retries apply
+    /// to the calls failing to connect, timing out or answered with a retryable status.
+    pub fn with_options(mut self, options: &OracleOptions) -> Result<Self, Box<str>> {
+        self.timeout = options.timeout;
+        self.retry_policy = options.retry_policy.clone();
+        self.headers = header_map(&options.headers)?;
+        Ok(self)
+    }
+
+    /// Sends the request to `path`, and sends it again for each retry.
+    fn post(&self, path: &str, body: &[u8]) -> io::Result<(u16, Vec<u8>)> {
+        let mut attempt = 0;
+        loop {
+            let response = self.send(path, body);
+            let retryable = match &response {
+                Ok((status, _)) => self.retry_policy.retry_statuses.contains(status),
+                Err(err) => matches!(
+                    err.kind(),
+                    io::ErrorKind::NotFound
+                        | io::ErrorKind::ConnectionRefused
+                        | io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                ),
+            };
+            if !retryable || attempt >= self.retry_policy.retries {
+                return response;
+            }
+
+            thread::sleep(self.retry_policy.delay(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// Sends an HTTP/1.1 POST request with a JSON body, and returns the status code and body of
+    /// the response.
+    fn send(&self, path: &str, body: &[u8]) -> io::Result<(u16, Vec<u8>)> {
+        let mut stream = DeadlineStream {
+            stream: UnixStream::connect(&self.socket)?,
+            deadline: Instant::now() + self.timeout,
+        };
+
+        let mut request = format!(
+            "POST {path} HTTP/1.1\r\n\
+             Host: localhost\r\n\
+             Content-Type: application/json\r\n\
+             Accept: application/json\r\n\
+             Content-Length: {}\r\n",
+            body.len()
+        )
+        .into_bytes();
+        for (name, value) in &self.headers {
+            request.extend_from_slice(name.as_str().as_bytes());
+            request.extend_from_slice(b": ");
+            request.extend_from_slice(value.as_bytes());
+            request.extend_from_slice(b"\r\n");
+        }
+        request.extend_from_slice(b"Connection: close\r\n\r\n");
+        request.extend_from_slice(body);
+        stream.write_all(&request)?;
+        stream.flush()?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)?;
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| invalid_data(format!("invalid status line {status_line:?}")))?;
+
+        let mut content_length = None;
+        let mut chunked = false;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                return Err(invalid_data("unexpected end of headers".to_string()));
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let Some((name, value)) = header.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<usize>().ok();
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            }
+        }
+
+        let mut body = Vec::new();
+        if chunked {
+            loop {
+                let mut size = String::new();
+                reader.read_line(&mut size)?;
+                let size = size.trim_end();
+                let size = size.split(';').next().unwrap_or_default();
+                let size = usize::from_str_radix(size, 16)
+                    .map_err(|_| invalid_data(format!("invalid chunk size {size:?}")))?;
+                let mut chunk = vec![0; size + 2];
+                reader.read_exact(&mut chunk)?;
+                if size == 0 {
+                    break;
+                }
+                body.extend_from_slice(&chunk[..size]);
+            }
+        } else if let Some(content_length) = content_length {
+            body.resize(content_length, 0);
+            reader.read_exact(&mut body)?;
+        } else {
+            reader.read_to_end(&mut body)?;
+        }
+
+        Ok((status, body))
+    }
+}
+
+/// Stream whose reads and writes time out once `deadline` is passed, rather than after a
+/// timeout for each of them.
+struct DeadlineStream {
+    stream: UnixStream,
+    deadline: Instant,
+}
+
+impl DeadlineStream {
+    fn remaining(&self) -> io::Result<Duration> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        Ok(remaining)
+    }
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(self.remaining()?))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for DeadlineStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(Some(self.remaining()?))?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl OracleTransport for UnixSocketTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let socket = self.socket.display();
+
+        let data = deserialize_cairo_serde(&self.configuration, &configuration.input, &mut data)
+            .map_err(|err| format!("Error deserializing input of {selector}: {err}"))?;
+        let (status, body) = self
+            .post(
+                &method_path(selector, service, method),
+                data.to_string().as_bytes(),
+            )
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                    format!("Oracle server on {socket} didn't respond in time.")
+                }
+                _ => format!(
+                    "Couldn't connect to oracle server on {socket}: {err}. Is the server running?"
+                ),
+            })?;
+        let body = String::from_utf8(body).map_err(|_| {
+            formatdoc! {
+                r#"
+                Response from oracle server can't be parsed as string."#
+            }
+        })?;
+
+        let body = json_body(
+            &body,
+            (200..300).contains(&status),
+            |body| configuration.fallible && body.get("error").is_some(),
+            &format!("on {socket}"),
+        )?;
+
+        json_answer(&self.configuration, configuration, selector, &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+
+    use super::*;
+    use crate::tests::configuration;
+
+    /// Answers `503` to the first request and `{"result": 5}` to the second one, and returns the
+    /// head of both.
+    fn serve(listener: UnixListener) -> thread::JoinHandle<Vec<String>> {
+        thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in [
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n{\"result\": 5}",
+            ] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                while reader.read_line(&mut request).unwrap() > 2 {}
+                let content_length = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .unwrap();
+                let mut body = vec![0; content_length.parse().unwrap()];
+                reader.read_exact(&mut body).unwrap();
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+                requests.push(request);
+            }
+            requests
+        })
+    }
+
+    #[test]
+    fn it_sends_headers_and_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("oracle.sock");
+        let server = serve(UnixListener::bind(&socket).unwrap());
+
+        let options = OracleOptions {
+            retry_policy: RetryPolicy {
+                retries: 1,
+                backoff: Duration::from_millis(1),
+                ..Default::default()
+            },
+            headers: vec![("Authorization".to_string(), "Bearer token".to_string())],
+            ..Default::default()
+        };
+        let oracle = UnixSocketTransport::new(&socket, configuration())
+            .with_options(&options)
+            .unwrap();
+        let output = oracle
+            .execute_hint("Oracle/inc", &[Felt252::from(4)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(5)]);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            assert!(
+                request.starts_with("POST /Oracle/inc HTTP/1.1\r\n"),
+                "{request}"
+            );
+            assert!(
+                request.contains("\r\nauthorization: Bearer token\r\n"),
+                "{request}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_times_out_slow_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("oracle.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        // Sends a byte of the response every 20 ms, each read being shorter than the timeout.
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            for byte in b"HTTP/1.1 200 OK\r\nX-Slow: "
+                .iter()
+                .chain([b'.'; 100].iter())
+            {
+                if stream.write_all(&[*byte]).is_err() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+
+        let oracle = UnixSocketTransport::new(&socket, configuration())
+            .with_timeout(Duration::from_millis(200));
+        let start = Instant::now();
+        let err = oracle
+            .execute_hint("Oracle/inc", &[Felt252::from(4)])
+            .unwrap_err();
+        assert!(err.contains("didn't respond in time"), "{err}");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
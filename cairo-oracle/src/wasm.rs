@@ -0,0 +1,304 @@
+//! Transport calling the functions exported by a WebAssembly module in-process.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use cairo_proto_serde::configuration::Configuration;
+use cairo_proto_serde::{decode_protobuf, deserialize_cairo_serde, encode_protobuf};
+use indoc::formatdoc;
+use serde_json::Value;
+use starknet_types_core::felt::Felt as Felt252;
+use wasmi::{Config, Engine, Instance, Linker, Module, Store};
+
+use crate::{json_answer, method_declaration, ok_answer, OracleTransport};
+
+/// Fuel given to a module for each hint by default, about one unit per instruction executed.
+const DEFAULT_FUEL: u64 = 1_000_000_000;
+
+/// Encoding of the requests and responses exchanged with a WebAssembly module.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum WasmEncoding {
+    /// The request as JSON, answered with `{"result": <response>}`.
+    #[default]
+    Json,
+    /// The request and response as Protobuf messages.
+    Protobuf,
+}
+
+impl FromStr for WasmEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(WasmEncoding::Json),
+            "protobuf" => Ok(WasmEncoding::Protobuf),
+            _ => Err(format!(
+                "{s} is not a valid WebAssembly oracle encoding, expected `json` or `protobuf`"
+            )),
+        }
+    }
+}
+
+/// Calls the function named after the service and method in a WebAssembly module, instantiated
+/// once without any import, so hints are sandboxed and deterministic.
+///
+/// The module must export its `memory`, an `alloc(len: i32) -> i32` function returning a buffer
+/// of `len` bytes, and per method a `<Service>/<method>(ptr: i32, len: i32) -> i64` function
+/// taking the request and returning `(ptr << 32) | len` of the response. If the module exports
+/// `dealloc(ptr: i32, len: i32)`, it is called to release the request and response buffers.
+///
+/// The module runs out of fuel, failing the hint, if it executes too many instructions, so that
+/// a module looping forever doesn't hang the program.
+pub struct WasmTransport {
+    store: Mutex<Store<()>>,
+    instance: Instance,
+    fuel: u64,
+    encoding: WasmEncoding,
+    configuration: Configuration,
+}
+
+impl WasmTransport {
+    pub fn new(
+        wasm: &[u8],
+        encoding: WasmEncoding,
+        configuration: Configuration,
+    ) -> Result<Self, Box<str>> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)
+            .map_err(|err| format!("Invalid WebAssembly oracle module: {err}"))?;
+        let mut store = Store::new(&engine, ());
+        store
+            .set_fuel(DEFAULT_FUEL)
+            .expect("fuel metering is enabled");
+        let instance = Linker::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|err| format!("Couldn't instantiate WebAssembly oracle module: {err}"))?;
+
+        Ok(Self {
+            store: Mutex::new(store),
+            instance,
+            fuel: DEFAULT_FUEL,
+            encoding,
+            configuration,
+        })
+    }
+
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        encoding: WasmEncoding,
+        configuration: Configuration,
+    ) -> Result<Self, Box<str>> {
+        let path = path.as_ref();
+        let wasm = std::fs::read(path).map_err(|err| {
+            format!(
+                "Couldn't read WebAssembly oracle module {}: {err}",
+                path.display()
+            )
+        })?;
+        Self::new(&wasm, encoding, configuration)
+    }
+
+    /// Fails the hints for which the module executes more than about `fuel` instructions.
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Calls the function exported as `method`, i.e. `<Service>/<method>`, with the request, and
+    /// returns the response.
+    fn call(&self, method: &str, request: &[u8]) -> Result<Vec<u8>, Box<str>> {
+        let call_error = |err: wasmi::Error| {
+            format!("Error calling {method} in WebAssembly oracle module: {err}")
+        };
+        let mut store = self.store.lock().unwrap_or_else(|err| err.into_inner());
+        store.set_fuel(self.fuel).expect("fuel metering is enabled");
+        let memory = self
+            .instance
+            .get_memory(&*store, "memory")
+            .ok_or("WebAssembly oracle module must export its memory")?;
+        let alloc = self
+            .instance
+            .get_typed_func::<i32, i32>(&*store, "alloc")
+            .map_err(call_error)?;
+        let function = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&*store, method)
+            .map_err(call_error)?;
+
+        let request_len = i32::try_from(request.len()).map_err(|_| "Request is too large")?;
+        let request_ptr = alloc.call(&mut *store, request_len).map_err(call_error)?;
+        memory
+            .write(&mut *store, request_ptr as u32 as usize, request)
+            .map_err(|err| format!("Error writing request of {method}: {err}"))?;
+
+        let response = function
+            .call(&mut *store, (request_ptr, request_len))
+            .map_err(call_error)?;
+        let (response_ptr, response_len) = ((response >> 32) as u32, response as u32);
+        let mut output = vec![0; response_len as usize];
+        memory
+            .read(&*store, response_ptr as usize, &mut output)
+            .map_err(|err| format!("Error reading response of {method}: {err}"))?;
+
+        if let Ok(dealloc) = self
+            .instance
+            .get_typed_func::<(i32, i32), ()>(&*store, "dealloc")
+        {
+            dealloc
+                .call(&mut *store, (request_ptr, request_len))
+                .and_then(|_| dealloc.call(&mut *store, (response_ptr as i32, response_len as i32)))
+                .map_err(call_error)?;
+        }
+
+        Ok(output)
+    }
+}
+
+impl OracleTransport for WasmTransport {
+    fn execute_hint(&self, selector: &str, mut data: &[Felt252]) -> Result<Vec<Felt252>, Box<str>> {
+        let (service, method, configuration) = method_declaration(&self.configuration, selector)?;
+        let method = format!("{service}/{method}");
+
+        match self.encoding {
+            WasmEncoding::Json => {
+                let data =
+                    deserialize_cairo_serde(&self.configuration, &configuration.input, &mut data)
+                        .map_err(|err| format!("Error deserializing input of {selector}: {err}"))?;
+                let body = self.call(&method, data.to_string().as_bytes())?;
+
+                let body = serde_json::from_slice::<Value>(&body).map_err(|_| {
+                    let body = String::from_utf8_lossy(&body);
+                    formatdoc! {
+                        r#"
+                        Received {body:?}.
+                        Error converting response from WebAssembly oracle module to JSON."#
+                    }
+                })?;
+
+                json_answer(&self.configuration, configuration, selector, &body)
+            }
+            WasmEncoding::Protobuf => {
+                let data = encode_protobuf(&self.configuration, &configuration.input, &mut data)
+                    .map_err(|err| format!("Error encoding input of {selector}: {err}"))?;
+                let body = self.call(&method, &data)?;
+
+                let output = decode_protobuf(&self.configuration, &configuration.output, &body)
+                    .map_err(|err| format!("Error decoding output of {selector}: {err}"))?;
+                Ok(ok_answer(configuration, output))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::configuration;
+
+    /// Answers `SqrtOracle/sqrt` with the request itself, `Oracle/sqrt` with a constant Protobuf
+    /// response, or `SqrtOracle/json_sqrt` with a constant JSON response.
+    const MODULE: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "{\"result\":{\"n\":42}}")
+            (data (i32.const 64) "\08\07")
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "SqrtOracle/sqrt") (param $ptr i32) (param $len i32) (result i64)
+                (i64.or
+                    (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len))))
+            (func (export "Oracle/sqrt") (param i32 i32) (result i64)
+                (i64.or (i64.shl (i64.const 64) (i64.const 32)) (i64.const 2)))
+            (func (export "SqrtOracle/json_sqrt") (param i32 i32) (result i64)
+                (i64.const 19))
+            (func (export "SqrtOracle/spin") (param i32 i32) (result i64)
+                (loop $forever (br $forever))
+                (i64.const 0)))
+    "#;
+
+    #[test]
+    fn it_calls_module_functions() {
+        let wasm = wat::parse_str(MODULE).unwrap();
+
+        let transport = WasmTransport::new(&wasm, WasmEncoding::Protobuf, configuration()).unwrap();
+        let output = transport
+            .execute_hint("sqrt", &[Felt252::from(1764)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(1764)]);
+
+        let mut configuration = configuration();
+        let methods = &mut configuration
+            .services
+            .get_mut("SqrtOracle")
+            .unwrap()
+            .methods;
+        let sqrt = methods.remove("sqrt").unwrap();
+        methods.insert("json_sqrt".into(), sqrt);
+        let transport = WasmTransport::new(&wasm, WasmEncoding::Json, configuration).unwrap();
+        let output = transport
+            .execute_hint("json_sqrt", &[Felt252::from(1764)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(42)]);
+    }
+
+    #[test]
+    fn it_calls_the_function_of_the_service() {
+        let wasm = wat::parse_str(MODULE).unwrap();
+        let mut configuration = configuration();
+        let sqrt = configuration.services["SqrtOracle"].methods["sqrt"].clone();
+        configuration
+            .services
+            .get_mut("Oracle")
+            .unwrap()
+            .methods
+            .insert("sqrt".into(), sqrt);
+
+        let transport = WasmTransport::new(&wasm, WasmEncoding::Protobuf, configuration).unwrap();
+        let output = transport
+            .execute_hint("SqrtOracle/sqrt", &[Felt252::from(1764)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(1764)]);
+        let output = transport
+            .execute_hint("Oracle/sqrt", &[Felt252::from(1764)])
+            .unwrap();
+        assert_eq!(output, vec![Felt252::from(7)]);
+    }
+
+    #[test]
+    fn it_runs_out_of_fuel() {
+        let wasm = wat::parse_str(MODULE).unwrap();
+        let mut configuration = configuration();
+        let methods = &mut configuration
+            .services
+            .get_mut("SqrtOracle")
+            .unwrap()
+            .methods;
+        let sqrt = methods["sqrt"].clone();
+        methods.insert("spin".into(), sqrt);
+
+        let transport = WasmTransport::new(&wasm, WasmEncoding::Protobuf, configuration)
+            .unwrap()
+            .with_fuel(10_000);
+        let err = transport
+            .execute_hint("spin", &[Felt252::from(1764)])
+            .unwrap_err();
+        assert!(err.contains("fuel"), "{err}");
+        // The fuel is given again to each hint.
+        assert!(transport
+            .execute_hint("sqrt", &[Felt252::from(1764)])
+            .is_ok());
+    }
+
+    #[test]
+    fn it_rejects_modules_with_imports() {
+        let wasm = wat::parse_str(r#"(module (import "env" "now" (func (result i64))))"#).unwrap();
+        assert!(WasmTransport::new(&wasm, WasmEncoding::Json, configuration()).is_err());
+    }
+}
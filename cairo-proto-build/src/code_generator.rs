@@ -2,7 +2,8 @@ use std::collections::{HashMap, HashSet};
 use std::iter;
 
 use cairo_proto_serde::configuration::{
-    Configuration, Field, FieldType, Mapping, MethodDeclaration,
+    selector, Configuration, Field, FieldType, IntegerEncoding, Mapping, MethodDeclaration,
+    PrimitiveType, Tag,
 };
 use heck::ToTitleCase;
 use itertools::{Either, Itertools};
@@ -12,11 +13,12 @@ use prost_types::field_descriptor_proto::{Label, Type};
 use prost_types::source_code_info::Location;
 use prost_types::{
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
-    FieldOptions, FileDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
+    FieldOptions, FileDescriptorProto, OneofDescriptorProto, ServiceDescriptorProto,
+    SourceCodeInfo,
 };
 
 use crate::ast::{Comments, Method, Service};
-use crate::extern_paths::ExternPaths;
+use crate::extern_paths::{is_cairo_type, ExternPaths};
 use crate::ident::{to_snake, to_upper_camel};
 use crate::message_graph::MessageGraph;
 use crate::Config;
@@ -162,6 +164,7 @@ impl<'a> CodeGenerator<'a> {
             let m = Mapping {
                 name: variant.proto_name.to_string().to_title_case(),
                 nb: variant.proto_number,
+                proto_name: Some(variant.proto_name.to_string()),
             };
             mappings_def.push(m);
             self.path.push(variant.path_idx as i32);
@@ -174,7 +177,8 @@ impl<'a> CodeGenerator<'a> {
         }
 
         self.path.pop();
-        self.serde_config.enums.insert(enum_name, mappings_def);
+        let enum_key = self.type_key(&enum_name);
+        self.serde_config.enums.insert(enum_key, mappings_def);
 
         self.depth -= 1;
 
@@ -270,7 +274,10 @@ impl<'a> CodeGenerator<'a> {
                 .as_ref()
                 .and_then(|type_name| map_types.get(type_name))
             {
-                Some((key, value)) => self.append_map_field(&fq_message_name, field, key, value),
+                Some((key, value)) => {
+                    let field_def = self.append_map_field(&fq_message_name, field, key, value);
+                    fields_def.push(field_def);
+                }
                 None => {
                     let field_def = self.append_field(&fq_message_name, field);
                     fields_def.push(field_def);
@@ -280,30 +287,30 @@ impl<'a> CodeGenerator<'a> {
         }
         self.path.pop();
 
-        let struct_key = if self.type_path.is_empty() {
-            format!("{}::{}", self.package, struct_name)
-        } else {
-            format!(
-                "{}::{}::{}",
-                self.package,
-                self.type_path.join("::").to_lowercase(),
-                struct_name
-            )
-        };
-
-        self.serde_config.messages.insert(struct_key, fields_def);
-
         self.path.push(8);
-        #[allow(clippy::never_loop)]
-        for (_idx, _oneof) in message.oneof_decl.iter().enumerate() {
-            panic!("oneof fields are not supported");
+        for (idx, oneof) in message.oneof_decl.iter().enumerate() {
+            // Synthetic oneofs of proto3 `optional` fields have no fields left in them.
+            let Some(fields) = oneof_fields.get_vec(&(idx as i32)) else {
+                continue;
+            };
+            self.path.push(idx as i32);
+            let field_def = self.append_oneof_field(&fq_message_name, oneof, fields);
+            fields_def.push(field_def);
+            self.path.pop();
         }
         self.path.pop();
 
+        let struct_key = self.type_key(&struct_name);
+        self.serde_config.messages.insert(struct_key, fields_def);
+
         self.depth -= 1;
         self.push_indent();
         self.code_buf.push_str("}\n");
 
+        if self.message_graph.is_recursive(&fq_message_name) {
+            self.append_box_serde(&struct_name);
+        }
+
         if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
             self.push_mod(&message_name);
             self.path.push(3);
@@ -321,10 +328,17 @@ impl<'a> CodeGenerator<'a> {
                 self.path.pop();
             }
             self.path.pop();
-            #[allow(clippy::never_loop)]
-            for (_idx, _oneof) in message.oneof_decl.into_iter().enumerate() {
-                panic!("oneof messages are not supported");
+
+            self.path.push(8);
+            for (idx, oneof) in message.oneof_decl.iter().enumerate() {
+                let Some(fields) = oneof_fields.get_vec(&(idx as i32)) else {
+                    continue;
+                };
+                self.path.push(idx as i32);
+                self.append_oneof(&fq_message_name, oneof, fields);
+                self.path.pop();
             }
+            self.path.pop();
 
             self.pop_mod();
         }
@@ -400,7 +414,7 @@ impl<'a> CodeGenerator<'a> {
             type_name.push_str("Option<");
         }
         if boxed {
-            panic!("boxed types not supported?");
+            type_name.push_str("Box<");
         }
         type_name.push_str(&ty);
         if boxed {
@@ -412,38 +426,162 @@ impl<'a> CodeGenerator<'a> {
         self.code_buf.push_str(&type_name);
         self.code_buf.push_str(",\n");
 
-        let ty_without_super = self.remove_super(&ty);
+        let field_ty = self.field_type(&field, &ty);
         if repeated {
             Field {
                 name: field_name,
-                ty: FieldType::Array(Box::new(ty_without_super.into())),
+                ty: FieldType::Array(Box::new(field_ty)),
+                tag: Some(tag(&field)),
             }
         } else if optional {
             Field {
                 name: field_name,
-                ty: FieldType::Option(Box::new(ty_without_super.into())),
-            }
-        } else if type_ == Type::Enum {
-            Field {
-                name: field_name,
-                ty: FieldType::Enum(ty_without_super),
+                ty: FieldType::Option(Box::new(field_ty)),
+                tag: Some(tag(&field)),
             }
         } else {
             Field {
                 name: field_name,
-                ty: ty_without_super.into(),
+                ty: field_ty,
+                tag: Some(tag(&field)),
+            }
+        }
+    }
+
+    /// Appends the struct field holding a oneof. None of the oneof fields may be set, so the
+    /// field is an `Option` of the enum generated by `append_oneof`.
+    fn append_oneof_field(
+        &mut self,
+        fq_message_name: &str,
+        oneof: &OneofDescriptorProto,
+        fields: &[(FieldDescriptorProto, usize)],
+    ) -> cairo_proto_serde::configuration::Field {
+        let field_name = to_snake(oneof.name());
+        let ty = self.resolve_ident(&format!("{}.{}", fq_message_name, oneof.name()));
+
+        debug!("    oneof: {:?}, type: {:?}", oneof.name(), ty);
+
+        self.push_indent();
+        self.code_buf
+            .push_str(&format!("pub {}: Option<{}>,\n", field_name, ty));
+
+        let variants = fields
+            .iter()
+            .map(|(field, _)| Field {
+                name: to_snake(field.name()),
+                ty: self.field_type(field, &self.resolve_type(field)),
+                tag: Some(tag(field)),
+            })
+            .collect();
+
+        Field::new(
+            field_name,
+            FieldType::Option(Box::new(FieldType::OneOf(variants))),
+        )
+    }
+
+    /// Appends the enum for a oneof, with one data-carrying variant per field.
+    fn append_oneof(
+        &mut self,
+        fq_message_name: &str,
+        oneof: &OneofDescriptorProto,
+        fields: &[(FieldDescriptorProto, usize)],
+    ) {
+        self.push_indent();
+        self.code_buf.push_str("#[derive(Drop, Serde)]\n");
+        self.push_indent();
+        self.code_buf.push_str("pub enum ");
+        self.code_buf.push_str(&to_upper_camel(oneof.name()));
+        self.code_buf.push_str(" {\n");
+
+        self.depth += 1;
+        for (field, _) in fields {
+            let mut ty = self.resolve_type(field);
+            if field.r#type() == Type::Message
+                && self
+                    .message_graph
+                    .is_nested(field.type_name(), fq_message_name)
+            {
+                ty = format!("Box<{}>", ty);
             }
+            self.push_indent();
+            self.code_buf
+                .push_str(&format!("{}: {},\n", to_upper_camel(field.name()), ty));
+        }
+        self.depth -= 1;
+
+        self.push_indent();
+        self.code_buf.push_str("}\n");
+    }
+
+    /// Appends a `Serde` implementation for boxed recursive fields. Boxes are transparent in the
+    /// serialized form, so the value is written and read as if it wasn't boxed.
+    fn append_box_serde(&mut self, struct_name: &str) {
+        let impl_code = format!(
+            "pub impl {name}BoxSerde of Serde<Box<{name}>> {{
+    fn serialize(self: @Box<{name}>, ref output: Array<felt252>) {{
+        Serde::serialize(self.as_snapshot().unbox(), ref output);
+    }}
+    fn deserialize(ref serialized: Span<felt252>) -> Option<Box<{name}>> {{
+        Option::Some(BoxTrait::new(Serde::deserialize(ref serialized)?))
+    }}
+}}
+",
+            name = struct_name
+        );
+        for line in impl_code.lines() {
+            self.push_indent();
+            self.code_buf.push_str(line);
+            self.code_buf.push('\n');
         }
     }
 
     fn append_map_field(
         &mut self,
         _fq_message_name: &str,
-        _field: FieldDescriptorProto,
-        _key: &FieldDescriptorProto,
-        _value: &FieldDescriptorProto,
-    ) {
-        todo!("cairo maps are not serializable");
+        field: FieldDescriptorProto,
+        key: &FieldDescriptorProto,
+        value: &FieldDescriptorProto,
+    ) -> cairo_proto_serde::configuration::Field {
+        let key_ty = self.resolve_type(key);
+        let value_ty = self.resolve_type(value);
+
+        debug!(
+            "    map field: {:?}, key: {:?}, value: {:?}",
+            field.name(),
+            key_ty,
+            value_ty
+        );
+
+        if self.deprecated(&field) {
+            self.push_indent();
+            self.code_buf.push_str("#[deprecated]\n");
+        }
+
+        // Cairo has no serializable map type, so entries are generated as an array of pairs.
+        let field_name = to_snake(field.name());
+        self.push_indent();
+        self.code_buf.push_str(&format!(
+            "pub {}: Array<({}, {})>,\n",
+            field_name, key_ty, value_ty
+        ));
+
+        Field {
+            name: field_name,
+            ty: FieldType::Map(
+                Box::new(Field {
+                    name: String::from("key"),
+                    ty: self.field_type(key, &key_ty),
+                    tag: Some(tag(key)),
+                }),
+                Box::new(Field {
+                    name: String::from("value"),
+                    ty: self.field_type(value, &value_ty),
+                    tag: Some(tag(value)),
+                }),
+            ),
+            tag: Some(tag(&field)),
+        }
     }
 
     fn location(&self) -> Option<&Location> {
@@ -516,6 +654,23 @@ impl<'a> CodeGenerator<'a> {
     }
 
     fn append_service_def(&mut self, service: Service) {
+        let fallible = service
+            .methods
+            .iter()
+            .any(|method| self.config.is_fallible(&service.name, &method.name));
+        // The error type is generated once per module, before its first fallible method.
+        let has_oracle_error = self
+            .serde_config
+            .services
+            .values()
+            .flat_map(|service| service.methods.values())
+            .any(|method| method.fallible);
+        if fallible && !has_oracle_error {
+            self.code_buf.push_str(
+                "#[derive(Drop, Serde)]\npub struct OracleError {\n    pub code: i64,\n    pub message: ByteArray,\n}\n",
+            );
+        }
+
         // Generate a trait for the service.
         self.code_buf.push_str("#[generate_trait]\n");
         self.code_buf.push_str(&format!(
@@ -524,12 +679,23 @@ impl<'a> CodeGenerator<'a> {
         ));
 
         let mut methods = HashMap::<String, MethodDeclaration>::new();
+        let grpc_service = if service.package.is_empty() {
+            service.proto_name.clone()
+        } else {
+            format!("{}.{}", service.package, service.proto_name)
+        };
 
         // Generate the service methods.
         for method in service.methods {
+            let fallible = self.config.is_fallible(&service.name, &method.name);
+            let output_type = if fallible {
+                format!("Result<{}, OracleError>", method.output_type)
+            } else {
+                method.output_type.clone()
+            };
             self.code_buf.push_str(&format!(
                 "    fn {}(arg: {}) -> {} {{",
-                method.name, method.input_type, method.output_type
+                method.name, method.input_type, output_type
             ));
 
             self.code_buf.push_str(&format!(
@@ -539,7 +705,7 @@ impl<'a> CodeGenerator<'a> {
         let mut result = cheatcode::<'{}'>(serialized.span());
         Serde::deserialize(ref result).unwrap()
 ",
-                method.name
+                selector(&service.name, &method.name)
             ));
 
             self.code_buf.push_str("    }\n");
@@ -550,6 +716,8 @@ impl<'a> CodeGenerator<'a> {
                 MethodDeclaration {
                     input: FieldType::Message(input_without_super),
                     output: FieldType::Message(output_without_super),
+                    grpc_path: Some(format!("/{}/{}", grpc_service, method.proto_name)),
+                    fallible,
                 },
             );
         }
@@ -652,11 +820,33 @@ impl<'a> CodeGenerator<'a> {
         }
 
         match field.r#type() {
-            Type::Message => true,
+            Type::Message if !is_cairo_type(field.type_name()) => true,
             _ => self.syntax == Syntax::Proto2,
         }
     }
 
+    /// Oracle.lock type of a single (not repeated or optional) value of the field, given its
+    /// Cairo type as returned by `resolve_type`.
+    fn field_type(&self, field: &FieldDescriptorProto, ty: &str) -> FieldType {
+        let ty_without_super = self.remove_super(ty);
+        if field.r#type() == Type::Enum {
+            FieldType::Enum(ty_without_super)
+        } else if field.r#type() == Type::Bytes {
+            FieldType::Primitive(PrimitiveType::BYTES)
+        } else {
+            ty_without_super.into()
+        }
+    }
+
+    /// Key of a message or enum defined at the current position in the Oracle.lock
+    /// configuration. Matches the path `resolve_ident` generates for it, minus `super::`.
+    fn type_key(&self, name: &str) -> String {
+        iter::once(self.package.clone())
+            .chain(self.type_path.iter().map(|module| to_snake(module)))
+            .chain(iter::once(name.to_string()))
+            .join("::")
+    }
+
     fn remove_super(&self, input: &str) -> String {
         input.split("super::").last().unwrap().to_string()
     }
@@ -809,6 +999,19 @@ struct EnumVariantMapping<'a> {
     generated_variant_name: String,
 }
 
+/// Protobuf field number and encoding of a field, for the binary wire format.
+fn tag(field: &FieldDescriptorProto) -> Tag {
+    let encoding = match field.r#type() {
+        Type::Fixed32 | Type::Fixed64 | Type::Sfixed32 | Type::Sfixed64 => IntegerEncoding::Fixed,
+        Type::Sint32 | Type::Sint64 => IntegerEncoding::ZigZag,
+        _ => IntegerEncoding::Varint,
+    };
+    Tag {
+        number: field.number() as u32,
+        encoding,
+    }
+}
+
 fn build_enum_value_mappings<'a>(
     generated_enum_name: &str,
     do_strip_enum_prefix: bool,
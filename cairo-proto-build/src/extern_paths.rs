@@ -17,6 +17,24 @@ fn validate_proto_path(path: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Messages from `cairo/types.proto` and the Cairo primitives they are generated as.
+pub const CAIRO_TYPES: &[(&str, &str)] = &[
+    (".cairo.U8", "u8"),
+    (".cairo.U16", "u16"),
+    (".cairo.U128", "u128"),
+    (".cairo.U256", "u256"),
+    (".cairo.I8", "i8"),
+    (".cairo.I16", "i16"),
+    (".cairo.I128", "i128"),
+];
+
+/// Returns `true` if the fully qualified Protobuf type is one of the Cairo integer types.
+pub fn is_cairo_type(pb_ident: &str) -> bool {
+    CAIRO_TYPES
+        .iter()
+        .any(|(proto_path, _)| *proto_path == pb_ident)
+}
+
 #[derive(Debug)]
 pub struct ExternPaths {
     extern_paths: HashMap<String, String>,
@@ -32,6 +50,10 @@ impl ExternPaths {
             extern_paths.insert(proto_path.clone(), rust_path.clone())?;
         }
 
+        for (proto_path, cairo_type) in CAIRO_TYPES {
+            extern_paths.insert(proto_path.to_string(), cairo_type.to_string())?;
+        }
+
         if prost_types {
             extern_paths.insert(".google.protobuf".to_string(), "::prost_types".to_string())?;
             extern_paths.insert(".google.protobuf.BoolValue".to_string(), "bool".to_string())?;
@@ -167,4 +189,14 @@ mod tests {
         case(".google.protobuf.Duration", "::prost_types::Duration");
         case(".google.protobuf.Empty", "()");
     }
+
+    #[test]
+    fn test_cairo_types() {
+        let paths = ExternPaths::new(&[], false).unwrap();
+
+        assert_eq!(paths.resolve_ident(".cairo.U256").unwrap(), "u256");
+        assert_eq!(paths.resolve_ident(".cairo.I8").unwrap(), "i8");
+        assert!(is_cairo_type(".cairo.U128"));
+        assert!(!is_cairo_type(".oracle.U128"));
+    }
 }
@@ -1,5 +1,6 @@
 use cairo_proto_serde::configuration::Configuration;
 use cairo_proto_serde::configuration::Field;
+use cairo_proto_serde::configuration::JsonMapping;
 use cairo_proto_serde::configuration::Mapping;
 use cairo_proto_serde::configuration::Service;
 use code_generator::CodeGenerator;
@@ -22,6 +23,12 @@ use std::io::{Error, ErrorKind};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Definitions of the Cairo integer types, importable as `cairo/types.proto`.
+const CAIRO_TYPES_PROTO: &str = include_str!("../proto/cairo/types.proto");
+
+/// Package of `cairo/types.proto`. No code is generated for it.
+const CAIRO_TYPES_PACKAGE: &str = "cairo";
+
 mod ast;
 mod code_generator;
 mod extern_paths;
@@ -135,6 +142,8 @@ pub struct Config {
     out_dir: Option<PathBuf>,
     oracle_lock: Option<PathBuf>,
     default_package_filename: String,
+    json_mapping: JsonMapping,
+    fallible: Vec<String>,
 }
 
 impl Config {
@@ -176,6 +185,35 @@ impl Config {
         self
     }
 
+    /// Configures the shape of the JSON exchanged with the oracle server, recorded in the .lock
+    /// file.
+    ///
+    /// If unset, defaults to [`JsonMapping::Cairo`].
+    pub fn json_mapping(&mut self, json_mapping: JsonMapping) -> &mut Self {
+        self.json_mapping = json_mapping;
+        self
+    }
+
+    /// Configures the methods whose generated Cairo function returns
+    /// `Result<Response, OracleError>`, named either as `Service` for all the methods of a
+    /// service or as `Service.method`. Generating fails if a name matches no service or method.
+    ///
+    /// If unset, no method is fallible.
+    pub fn fallible<I, S>(&mut self, methods: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.fallible = methods.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn is_fallible(&self, service: &str, method: &str) -> bool {
+        self.fallible
+            .iter()
+            .any(|name| *name == service || *name == format!("{service}.{method}"))
+    }
+
     /// Compile `.proto` files into Rust files during a Cargo build with additional code generator
     /// configuration options.
     ///
@@ -221,6 +259,11 @@ impl Config {
             cmd.arg("-I").arg(protoc_include);
         }
 
+        let cairo_include = tmp.path().join("include");
+        fs::create_dir_all(cairo_include.join("cairo"))?;
+        fs::write(cairo_include.join("cairo/types.proto"), CAIRO_TYPES_PROTO)?;
+        cmd.arg("-I").arg(&cairo_include);
+
         for proto in protos {
             cmd.arg(proto.as_ref());
         }
@@ -369,6 +412,9 @@ impl Config {
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
 
         for (request_module, request_fd) in requests {
+            if request_fd.package() == CAIRO_TYPES_PACKAGE {
+                continue;
+            }
             // Only record packages that have services
             if !request_fd.service.is_empty() {
                 packages.insert(request_module.clone(), request_fd.package().to_string());
@@ -377,7 +423,11 @@ impl Config {
                 modules.entry(request_module.clone()).or_insert_with(|| {
                     let mut init_buf = String::new();
                     Config::append_header(&mut init_buf);
-                    (init_buf, Configuration::default())
+                    let config = Configuration {
+                        json_mapping: self.json_mapping,
+                        ..Default::default()
+                    };
+                    (init_buf, config)
                 });
             CodeGenerator::generate(
                 self,
@@ -393,6 +443,24 @@ impl Config {
             }
         }
 
+        for name in &self.fallible {
+            let (service, method) = match name.split_once('.') {
+                Some((service, method)) => (service, Some(method)),
+                None => (name.as_str(), None),
+            };
+            let declared = modules.values().any(|(_, configuration)| {
+                configuration.services.get(service).is_some_and(|service| {
+                    method.map_or(true, |method| service.methods.contains_key(method))
+                })
+            });
+            if !declared {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Fallible oracle method {name:?} is not declared in the .proto files"),
+                ));
+            }
+        }
+
         for p in protos {
             let path = p.as_ref().to_str().unwrap().to_string();
             let mut super_enums: HashMap<String, Vec<Mapping>> = HashMap::new();
@@ -406,9 +474,7 @@ impl Config {
                 }
 
                 for (name, v) in &content.1.enums {
-                    let k = format!("{}::{}", module.components[0], name);
-                    super_enums.insert(k, v.to_owned());
-                    // super_enums.insert(name.clone(), v.to_owned());
+                    super_enums.insert(name.clone(), v.to_owned());
                 }
 
                 for (name, v) in &content.1.messages {
@@ -468,6 +534,8 @@ impl default::Default for Config {
             out_dir: None,
             oracle_lock: None,
             default_package_filename: String::from("oracle"),
+            json_mapping: JsonMapping::default(),
+            fallible: Vec::new(),
         }
     }
 }
@@ -519,3 +587,225 @@ pub fn protoc_include_from_env() -> Option<PathBuf> {
 
     Some(protoc_include)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_proto_serde::configuration::FieldType;
+    use protobuf::Message as _;
+
+    /// Generates the Cairo code and the Oracle.lock configuration of the `oracle` package
+    /// defined by `proto`.
+    fn generate(config: &mut Config, proto: &str) -> std::io::Result<(String, Configuration)> {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("cairo")).unwrap();
+        fs::write(dir.path().join("cairo/types.proto"), CAIRO_TYPES_PROTO).unwrap();
+        let path = dir.path().join("oracle.proto");
+        fs::write(&path, proto).unwrap();
+
+        let fds = protobuf_parse::Parser::new()
+            .pure()
+            .include(dir.path())
+            .input(&path)
+            .file_descriptor_set()
+            .unwrap();
+        let fds = FileDescriptorSet::decode(fds.write_to_bytes().unwrap().as_slice()).unwrap();
+        let requests = fds
+            .file
+            .into_iter()
+            .map(|file| (Module::from_protobuf_package_name(file.package()), file))
+            .collect();
+        let mut modules = config.generate(&[&path], requests)?;
+        Ok(modules.remove(&Module::from_parts(["oracle"])).unwrap())
+    }
+
+    #[test]
+    fn it_generates_maps_oneofs_and_bytes() {
+        let (code, configuration) = generate(
+            &mut Config::new(),
+            r#"
+            syntax = "proto3";
+            package oracle;
+
+            message Request {
+                map<string, sint64> balances = 1;
+                bytes payload = 2;
+                oneof result {
+                    uint64 found = 3;
+                    string error = 4;
+                }
+                fixed32 id = 5;
+            }
+            message Response {
+                uint64 n = 1;
+            }
+            service Oracle {
+                rpc Lookup(Request) returns (Response);
+            }
+            "#,
+        )
+        .unwrap();
+
+        for expected in [
+            "pub balances: Array<(ByteArray, i64)>,",
+            "pub payload: ByteArray,",
+            "pub id: u32,",
+            "pub result: Option<super::oracle::request::Result>,",
+            "pub mod request {",
+            "pub enum Result {\n        Found: u64,\n        Error: ByteArray,\n    }",
+            "fn lookup(arg: super::oracle::Request) -> super::oracle::Response {",
+            "cheatcode::<'Oracle/lookup'>",
+        ] {
+            assert!(code.contains(expected), "{expected:?} not in {code}");
+        }
+
+        let expected: Configuration = serde_json::from_value(serde_json::json!({
+            "enums": {},
+            "messages": {
+                "oracle::Request": [
+                    {"name": "balances", "ty": {"map": [
+                        {"name": "key", "ty": {"primitive": "bytearray"}, "tag": {"number": 1}},
+                        {"name": "value", "ty": {"primitive": "i64"},
+                            "tag": {"number": 2, "encoding": "zigzag"}}
+                    ]}, "tag": {"number": 1}},
+                    {"name": "payload", "ty": {"primitive": "bytes"}, "tag": {"number": 2}},
+                    {"name": "id", "ty": {"primitive": "u32"},
+                        "tag": {"number": 5, "encoding": "fixed"}},
+                    {"name": "result", "ty": {"option": {"oneof": [
+                        {"name": "found", "ty": {"primitive": "u64"}, "tag": {"number": 3}},
+                        {"name": "error", "ty": {"primitive": "bytearray"}, "tag": {"number": 4}}
+                    ]}}}
+                ],
+                "oracle::Response": [
+                    {"name": "n", "ty": {"primitive": "u64"}, "tag": {"number": 1}}
+                ]
+            },
+            "services": {
+                "Oracle": {
+                    "lookup": {
+                        "input": {"message": "oracle::Request"},
+                        "output": {"message": "oracle::Response"},
+                        "grpc_path": "/oracle.Oracle/Lookup"
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(configuration, expected);
+    }
+
+    #[test]
+    fn it_boxes_recursive_messages() {
+        let (code, configuration) = generate(
+            &mut Config::new(),
+            r#"
+            syntax = "proto3";
+            package oracle;
+
+            message Node {
+                uint64 value = 1;
+                Node next = 2;
+                repeated Node children = 3;
+            }
+            message Even {
+                Odd next = 1;
+            }
+            message Odd {
+                Even next = 1;
+            }
+            message Leaf {
+                Node node = 1;
+            }
+            service Oracle {
+                rpc Walk(Node) returns (Leaf);
+            }
+            "#,
+        )
+        .unwrap();
+
+        for expected in [
+            "pub next: Option<Box<super::oracle::Node>>,",
+            "pub children: Array<super::oracle::Node>,",
+            "pub next: Option<Box<super::oracle::Odd>>,",
+            "pub next: Option<Box<super::oracle::Even>>,",
+            "pub node: Option<super::oracle::Node>,",
+            "pub impl EvenBoxSerde of Serde<Box<Even>> {",
+            "pub impl OddBoxSerde of Serde<Box<Odd>> {",
+            "pub impl NodeBoxSerde of Serde<Box<Node>> {
+    fn serialize(self: @Box<Node>, ref output: Array<felt252>) {
+        Serde::serialize(self.as_snapshot().unbox(), ref output);
+    }
+    fn deserialize(ref serialized: Span<felt252>) -> Option<Box<Node>> {
+        Option::Some(BoxTrait::new(Serde::deserialize(ref serialized)?))
+    }
+}
+",
+        ] {
+            assert!(code.contains(expected), "{expected:?} not in {code}");
+        }
+        assert!(!code.contains("LeafBoxSerde"));
+
+        // Boxes are transparent in the serialized form.
+        let node = &configuration.messages["oracle::Node"];
+        assert_eq!(
+            node[1].ty,
+            FieldType::Option(Box::new(FieldType::Message("oracle::Node".into())))
+        );
+    }
+
+    #[test]
+    fn it_generates_fallible_methods() {
+        let proto = r#"
+            syntax = "proto3";
+            package oracle;
+
+            message Request {
+                uint64 n = 1;
+            }
+            message Response {
+                uint64 n = 1;
+            }
+            service SqrtOracle {
+                rpc Sqrt(Request) returns (Response);
+                rpc Cbrt(Request) returns (Response);
+            }
+            service PriceFeed {
+                rpc Price(Request) returns (Response);
+            }
+            "#;
+        let (code, configuration) = generate(
+            Config::new().fallible(["SqrtOracle.sqrt", "PriceFeed"]),
+            proto,
+        )
+        .unwrap();
+
+        // The error type is shared by the fallible methods of all the services of the module.
+        let oracle_error = "#[derive(Drop, Serde)]
+pub struct OracleError {
+    pub code: i64,
+    pub message: ByteArray,
+}
+";
+        assert_eq!(code.matches(oracle_error).count(), 1, "{code}");
+        for expected in [
+            "fn sqrt(arg: super::oracle::Request) -> Result<super::oracle::Response, OracleError> {",
+            "fn cbrt(arg: super::oracle::Request) -> super::oracle::Response {",
+            "fn price(arg: super::oracle::Request) -> Result<super::oracle::Response, OracleError> {",
+        ] {
+            assert!(code.contains(expected), "{expected:?} not in {code}");
+        }
+
+        let fallible =
+            |service: &str, method: &str| configuration.services[service].methods[method].fallible;
+        assert!(fallible("SqrtOracle", "sqrt"));
+        assert!(!fallible("SqrtOracle", "cbrt"));
+        assert!(fallible("PriceFeed", "price"));
+
+        let (code, _) = generate(&mut Config::new(), proto).unwrap();
+        assert!(!code.contains("OracleError"));
+
+        for unknown in ["SqrtOracle.sqr", "Clock"] {
+            assert!(generate(Config::new().fallible([unknown]), proto).is_err());
+        }
+    }
+}
@@ -84,4 +84,70 @@ impl MessageGraph {
 
         has_path_connecting(&self.graph, outer, inner, None)
     }
+
+    /// Returns true if message type `message` is part of a cycle, i.e. fields of some message
+    /// nested in it are boxed to refer back to `message`.
+    pub fn is_recursive(&self, message: &str) -> bool {
+        let message = match self.index.get(message) {
+            Some(message) => *message,
+            None => return false,
+        };
+
+        self.graph
+            .neighbors(message)
+            .any(|nested| has_path_connecting(&self.graph, nested, message, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::FieldDescriptorProto;
+
+    use super::*;
+
+    /// Message `name` with one field of each of the message types `fields`, and one repeated
+    /// field of each of the message types `repeated`.
+    fn message(name: &str, fields: &[&str], repeated: &[&str]) -> DescriptorProto {
+        let field = |ty: &&str, label: Label| FieldDescriptorProto {
+            r#type: Some(Type::Message as i32),
+            label: Some(label as i32),
+            type_name: Some(format!(".test.{ty}")),
+            ..Default::default()
+        };
+        DescriptorProto {
+            name: Some(name.to_string()),
+            field: fields
+                .iter()
+                .map(|ty| field(ty, Label::Optional))
+                .chain(repeated.iter().map(|ty| field(ty, Label::Repeated)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_recursive() {
+        let file = FileDescriptorProto {
+            package: Some("test".to_string()),
+            message_type: vec![
+                message("Node", &["Node"], &[]),
+                message("Even", &["Odd"], &[]),
+                message("Odd", &["Even"], &[]),
+                message("Leaf", &["Node", "Even"], &[]),
+                message("List", &[], &["List"]),
+            ],
+            ..Default::default()
+        };
+        let graph = MessageGraph::new(std::iter::once(&file)).unwrap();
+
+        assert!(graph.is_recursive(".test.Node"));
+        assert!(graph.is_recursive(".test.Even"));
+        assert!(graph.is_recursive(".test.Odd"));
+        // Messages referring to recursive ones aren't recursive themselves.
+        assert!(!graph.is_recursive(".test.Leaf"));
+        // Repeated fields are arrays, which need no box.
+        assert!(!graph.is_recursive(".test.List"));
+        assert!(!graph.is_recursive(".test.Unknown"));
+    }
 }
@@ -1,23 +1,113 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Configuration {
     pub enums: BTreeMap<String, Vec<Mapping>>,
     pub messages: BTreeMap<String, Vec<Field>>,
     pub services: BTreeMap<String, Service>,
+    #[serde(default, skip_serializing_if = "JsonMapping::is_cairo")]
+    pub json_mapping: JsonMapping,
+}
+
+/// Maximum length of a Cairo short string, and so of a cheatcode selector.
+const SHORT_STRING_MAX_LEN: usize = 31;
+
+/// Selector of the cheatcode calling `method` of `service`: `Service/method`, or its hashed
+/// form `#<hash>` when it's longer than a Cairo short string.
+pub fn selector(service: &str, method: &str) -> String {
+    let selector = format!("{service}/{method}");
+    if selector.len() <= SHORT_STRING_MAX_LEN {
+        return selector;
+    }
+
+    // 64-bit FNV-1a, which must stay the same for the selectors generated by earlier versions.
+    let hash = selector.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("#{hash:016x}")
+}
+
+impl Configuration {
+    /// Service, method and declaration of the method called by the cheatcode `selector`,
+    /// either service-qualified by `selector` or, as generated by earlier versions, the method
+    /// alone, looked up in all the services. A method alone declared by several services is
+    /// ambiguous, and `None` is returned.
+    pub fn method(&self, selector: &str) -> Option<(&str, &str, &MethodDeclaration)> {
+        if let Some((service, method)) = selector.split_once('/') {
+            let (service, methods) = self.services.get_key_value(service)?;
+            let (method, declaration) = methods.methods.get_key_value(method)?;
+            return Some((service, method, declaration));
+        }
+
+        let mut methods = self.services.iter().flat_map(|(service, methods)| {
+            methods
+                .methods
+                .iter()
+                .map(move |(method, declaration)| (service.as_str(), method.as_str(), declaration))
+        });
+        if selector.starts_with('#') {
+            methods.find(|(service, method, _)| self::selector(service, method) == selector)
+        } else {
+            let mut methods = methods.filter(|(_, method, _)| *method == selector);
+            let found = methods.next()?;
+            methods.next().is_none().then_some(found)
+        }
+    }
+}
+
+/// Shape of the JSON exchanged with the oracle server.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonMapping {
+    /// Field names as in Cairo, 64-bit integers as numbers and enums as their numbers.
+    #[default]
+    Cairo,
+    /// The canonical proto3 JSON mapping: lowerCamelCase field names, 64-bit integers as
+    /// strings, enums as their names and oneof fields inlined in the message.
+    Proto3,
+}
+
+impl JsonMapping {
+    fn is_cairo(&self) -> bool {
+        *self == JsonMapping::Cairo
+    }
+}
+
+impl FromStr for JsonMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cairo" => Ok(JsonMapping::Cairo),
+            "proto3" => Ok(JsonMapping::Proto3),
+            _ => Err(format!(
+                "{s} is not a valid JSON mapping, expected `cairo` or `proto3`"
+            )),
+        }
+    }
 }
 
 // primitive types supported by both Protocol Buffers and Cairo
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum PrimitiveType {
+    U8,
+    U16,
     U64,
     U32,
+    U128,
+    U256,
+    I8,
+    I16,
     I32,
     I64,
+    I128,
     BOOL,
     BYTEARRAY,
+    /// Protobuf `bytes`, a `ByteArray` in Cairo holding arbitrary, not necessarily UTF-8, data.
+    BYTES,
     FELT252,
 }
 
@@ -29,18 +119,67 @@ pub enum FieldType {
     Enum(String),
     Option(Box<FieldType>),
     Array(Box<FieldType>),
+    /// A Protobuf `map<K, V>`, represented in Cairo as `Array<(K, V)>` and in JSON as an object.
+    /// The fields are the `key` and `value` of the map entries.
+    Map(Box<Field>, Box<Field>),
+    /// A Protobuf `oneof`, represented in Cairo as an enum with one variant per field and in
+    /// JSON as an object with exactly one key, the name of the field that is set.
+    OneOf(Vec<Field>),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Field {
     pub name: String,
     pub ty: FieldType,
+    /// Protobuf field number and encoding, used by the binary wire format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Tag>,
+}
+
+impl Field {
+    /// Field without a Protobuf tag, enough to exchange it as JSON.
+    pub fn new(name: impl Into<String>, ty: FieldType) -> Self {
+        Field {
+            name: name.into(),
+            ty,
+            tag: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Tag {
+    pub number: u32,
+    #[serde(default, skip_serializing_if = "IntegerEncoding::is_varint")]
+    pub encoding: IntegerEncoding,
+}
+
+/// Wire encoding of an integer field, which the Cairo type alone doesn't determine.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegerEncoding {
+    /// `int32`, `int64`, `uint32`, `uint64`, `bool` and enums.
+    #[default]
+    Varint,
+    /// `fixed32`, `fixed64`, `sfixed32` and `sfixed64`.
+    Fixed,
+    /// `sint32` and `sint64`.
+    ZigZag,
+}
+
+impl IntegerEncoding {
+    fn is_varint(&self) -> bool {
+        *self == IntegerEncoding::Varint
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Mapping {
     pub name: String,
     pub nb: i32,
+    /// Name of the value in the `.proto` file, e.g. `COLOR_RED`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proto_name: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -53,15 +192,29 @@ pub struct Service {
 pub struct MethodDeclaration {
     pub input: FieldType,
     pub output: FieldType,
+    /// Path of the method in gRPC requests, `/package.Service/Method`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc_path: Option<String>,
+    /// Whether the generated Cairo function returns `Result<Response, OracleError>`, with the
+    /// errors answered by the oracle as `Err`, instead of the response.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fallible: bool,
 }
 
 impl From<String> for FieldType {
     fn from(value: String) -> Self {
         match value.as_ref() {
+            "u8" => FieldType::Primitive(PrimitiveType::U8),
+            "u16" => FieldType::Primitive(PrimitiveType::U16),
             "u64" => FieldType::Primitive(PrimitiveType::U64),
             "u32" => FieldType::Primitive(PrimitiveType::U32),
+            "u128" => FieldType::Primitive(PrimitiveType::U128),
+            "u256" => FieldType::Primitive(PrimitiveType::U256),
+            "i8" => FieldType::Primitive(PrimitiveType::I8),
+            "i16" => FieldType::Primitive(PrimitiveType::I16),
             "i32" => FieldType::Primitive(PrimitiveType::I32),
             "i64" => FieldType::Primitive(PrimitiveType::I64),
+            "i128" => FieldType::Primitive(PrimitiveType::I128),
             "bool" => FieldType::Primitive(PrimitiveType::BOOL),
             "ByteArray" => FieldType::Primitive(PrimitiveType::BYTEARRAY),
             "felt252" => FieldType::Primitive(PrimitiveType::FELT252),
@@ -69,3 +222,36 @@ impl From<String> for FieldType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_looks_up_methods_by_selector() {
+        let configuration: Configuration = serde_json::from_str(
+            r#"{"enums":{},"messages":{},"services":{
+                "Prices":{"get":{"input":{"primitive":"u64"},"output":{"primitive":"u64"}}},
+                "StorageProofsOfTheAccountsOracle":{"get":{"input":{"primitive":"u64"},"output":{"primitive":"u64"}}}
+            }}"#,
+        )
+        .unwrap();
+
+        assert_eq!(selector("Prices", "get"), "Prices/get");
+        let long = selector("StorageProofsOfTheAccountsOracle", "get");
+        assert!(long.starts_with('#') && long.len() <= SHORT_STRING_MAX_LEN);
+
+        let (service, method, _) = configuration.method("Prices/get").unwrap();
+        assert_eq!((service, method), ("Prices", "get"));
+        let (service, _, _) = configuration.method(&long).unwrap();
+        assert_eq!(service, "StorageProofsOfTheAccountsOracle");
+        // Both services declare `get`, so it can't be called without its service.
+        assert!(configuration.method("get").is_none());
+        let mut prices = configuration.clone();
+        prices.services.remove("StorageProofsOfTheAccountsOracle");
+        let (service, _, _) = prices.method("get").unwrap();
+        assert_eq!(service, "Prices");
+        assert!(configuration.method("Prices/set").is_none());
+        assert!(configuration.method("#0000000000000000").is_none());
+    }
+}
@@ -0,0 +1,29 @@
+use thiserror::Error;
+
+use crate::configuration::FieldType;
+
+/// Error raised while converting between JSON values and Cairo Serde felts.
+///
+/// Every variant carries the path of the offending field, starting with the name of the
+/// top-level type, for example `shirts::Request.inner.color` or `Request.y[2]`.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum SerdeError {
+    #[error("{path}: message `{message}` not found in configuration")]
+    UnknownMessage { path: String, message: String },
+    #[error("{path}: enum `{name}` not found in configuration")]
+    UnknownEnum { path: String, name: String },
+    #[error("{path}: field missing from value object, expected {expected:?}")]
+    MissingField { path: String, expected: FieldType },
+    #[error("{path}: value {value} can't be converted to {expected:?}")]
+    InvalidValue {
+        path: String,
+        expected: FieldType,
+        value: String,
+    },
+    #[error("{path}: input ended while reading {expected:?}")]
+    UnexpectedEnd { path: String, expected: FieldType },
+    #[error("{path}: field number missing from configuration, regenerate the .lock file")]
+    MissingFieldNumber { path: String },
+    #[error("{path}: invalid Protobuf wire format, {reason}")]
+    InvalidProtobuf { path: String, reason: String },
+}
@@ -1,4 +1,8 @@
-use crate::configuration::{Configuration, FieldType, PrimitiveType};
+use crate::configuration::{Configuration, Field, FieldType, JsonMapping, Mapping, PrimitiveType};
+pub use crate::error::SerdeError;
+pub use crate::protobuf::{decode_protobuf, encode_protobuf};
+use base64::prelude::*;
+use num_bigint::{BigInt, BigUint};
 use num_traits::One;
 use num_traits::ToPrimitive;
 use num_traits::Zero;
@@ -6,104 +10,326 @@ use serde_json::{json, Map, Value};
 use starknet_types_core::felt::Felt as Felt252;
 
 pub mod configuration;
+mod error;
+mod proto3;
+mod protobuf;
 
-fn serialize_primitive(ty: &PrimitiveType, value: &Value) -> Vec<Felt252> {
+fn invalid_value(path: &str, expected: FieldType, value: impl ToString) -> SerdeError {
+    SerdeError::InvalidValue {
+        path: path.to_string(),
+        expected,
+        value: value.to_string(),
+    }
+}
+
+/// Name of the top-level value, used as the root of the paths reported in errors.
+fn root_path(ty: &FieldType) -> String {
+    match ty {
+        FieldType::Message(name) | FieldType::Enum(name) => name.clone(),
+        _ => String::from("value"),
+    }
+}
+
+fn message_config<'a>(
+    config: &'a Configuration,
+    message_ty: &str,
+    path: &str,
+) -> Result<&'a Vec<Field>, SerdeError> {
+    config
+        .messages
+        .get(message_ty)
+        .ok_or_else(|| SerdeError::UnknownMessage {
+            path: path.to_string(),
+            message: message_ty.to_string(),
+        })
+}
+
+fn enum_config<'a>(
+    config: &'a Configuration,
+    enum_ty: &str,
+    path: &str,
+) -> Result<&'a Vec<Mapping>, SerdeError> {
+    config
+        .enums
+        .get(enum_ty)
+        .ok_or_else(|| SerdeError::UnknownEnum {
+            path: path.to_string(),
+            name: enum_ty.to_string(),
+        })
+}
+
+/// Reads an integer that may not fit in a JSON number. Accepts numbers as well as decimal or
+/// `0x`-prefixed hexadecimal strings.
+fn parse_integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| n.as_u64().map(BigInt::from)),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+/// Interprets a field element as a signed integer, the way Cairo stores signed types: elements
+/// above P/2 encode the negative number `x - P`.
+fn felt_to_signed(felt: &Felt252) -> BigInt {
+    let num = felt.to_bigint();
+    let prime = Felt252::MAX.to_bigint() + 1;
+    if num > &prime / 2 {
+        num - prime
+    } else {
+        num
+    }
+}
+
+/// Converts a JSON object key to the value it stands for, e.g. `"42"` to `42` for integer keys.
+fn parse_map_key(key: &str, key_ty: &FieldType) -> Value {
+    match key_ty {
+        FieldType::Primitive(
+            PrimitiveType::BYTEARRAY
+            | PrimitiveType::FELT252
+            | PrimitiveType::U128
+            | PrimitiveType::U256
+            | PrimitiveType::I128,
+        ) => Value::String(key.to_string()),
+        _ => serde_json::from_str(key).unwrap_or_else(|_| Value::String(key.to_string())),
+    }
+}
+
+/// Converts a deserialized map key to a JSON object key.
+fn format_map_key(key: Value) -> String {
+    match key {
+        Value::String(s) => s,
+        key => key.to_string(),
+    }
+}
+
+/// Decodes a JSON string holding `bytes` as standard base64.
+fn parse_bytes(s: &str) -> Option<Vec<u8>> {
+    BASE64_STANDARD.decode(s).ok()
+}
+
+/// Encodes bytes the way Cairo serializes a `ByteArray`: the number of full 31-byte words, the
+/// words, the pending word and its length.
+pub fn serialize_byte_array(bytes: &[u8]) -> Vec<Felt252> {
+    let mut p = Vec::new();
+
+    let total_length = bytes.len() / 31;
+    p.push(Felt252::from(total_length));
+
+    bytes
+        .chunks(31)
+        .for_each(|v| p.push(Felt252::from_bytes_be_slice(v)));
+
+    let last_row_length = bytes.len() % 31;
+    if last_row_length == 0 {
+        p.push(Felt252::from(0));
+    }
+    p.push(Felt252::from(last_row_length));
+    p
+}
+
+/// Reads the bytes of a Cairo `ByteArray` whose number of full words, `len`, was already taken
+/// off the input.
+fn deserialize_byte_array(
+    len: Felt252,
+    value: &mut &[Felt252],
+    path: &str,
+    expected: &FieldType,
+) -> Result<Vec<u8>, SerdeError> {
+    let data_len = len
+        .to_usize()
+        .ok_or_else(|| invalid_value(path, expected.clone(), len))?;
+    if value.len() < data_len + 2 {
+        return Err(SerdeError::UnexpectedEnd {
+            path: path.to_string(),
+            expected: expected.clone(),
+        });
+    }
+    let data = &value[0..data_len];
+    let pending_word = value[data_len];
+    let pending_word_len = value[data_len + 1]
+        .to_usize()
+        .filter(|len| *len < 31)
+        .ok_or_else(|| invalid_value(path, expected.clone(), value[data_len + 1]))?;
+    let trim_len = 32 - pending_word_len;
+    *value = &value[(data_len + 2)..];
+
+    let mut v = Vec::<u8>::with_capacity(31 * data.len());
+    for felt in data {
+        v.extend_from_slice(&felt.to_bytes_be()[1..]);
+    }
+    v.extend_from_slice(&pending_word.to_bytes_be()[trim_len..]);
+    Ok(v)
+}
+
+/// Takes the first felt off the input, failing if there is none left.
+fn next_felt(
+    value: &mut &[Felt252],
+    path: &str,
+    expected: &FieldType,
+) -> Result<Felt252, SerdeError> {
+    let (first, rest) = value
+        .split_first()
+        .ok_or_else(|| SerdeError::UnexpectedEnd {
+            path: path.to_string(),
+            expected: expected.clone(),
+        })?;
+    *value = rest;
+    Ok(*first)
+}
+
+fn serialize_primitive(
+    ty: &PrimitiveType,
+    value: &Value,
+    path: &str,
+) -> Result<Vec<Felt252>, SerdeError> {
+    let invalid = || invalid_value(path, FieldType::Primitive(ty.clone()), value);
     let element = match ty {
         PrimitiveType::FELT252 => match value {
-            Value::String(s) => Felt252::from_hex(s).expect("Invalid FELT252 hex string"),
-            _ => panic!("FELT252 must be a string"),
+            Value::String(s) => Felt252::from_hex(s).map_err(|_| invalid())?,
+            _ => return Err(invalid()),
         },
-        PrimitiveType::U64 => Felt252::from(
+        PrimitiveType::U8 => Felt252::from(
             value
                 .as_u64()
-                .expect(format!("Error converting {value:?} to u64").as_str()),
+                .and_then(|v| u8::try_from(v).ok())
+                .ok_or_else(invalid)?,
         ),
-        PrimitiveType::U32 => Felt252::from(
+        PrimitiveType::U16 => Felt252::from(
             value
                 .as_u64()
-                .expect(format!("Error converting {value:?} to u64").as_str()),
+                .and_then(|v| u16::try_from(v).ok())
+                .ok_or_else(invalid)?,
         ),
-        PrimitiveType::I32 => Felt252::from(
+        PrimitiveType::U64 => Felt252::from(value.as_u64().ok_or_else(invalid)?),
+        PrimitiveType::U128 => Felt252::from(
+            parse_integer(value)
+                .and_then(|v| u128::try_from(v).ok())
+                .ok_or_else(invalid)?,
+        ),
+        PrimitiveType::U256 => {
+            // u256 is a struct of two u128 limbs, serialized low first.
+            let v = parse_integer(value)
+                .and_then(|v| BigUint::try_from(v).ok())
+                .filter(|v| v.bits() <= 256)
+                .ok_or_else(invalid)?;
+            let mask = (BigUint::one() << 128u32) - BigUint::one();
+            let low = &v & &mask;
+            let high = v >> 128u32;
+            return Ok(vec![Felt252::from(low), Felt252::from(high)]);
+        }
+        PrimitiveType::I8 => Felt252::from(
             value
                 .as_i64()
-                .expect(format!("Error converting {value:?} to i64").as_str()),
+                .and_then(|v| i8::try_from(v).ok())
+                .ok_or_else(invalid)?,
         ),
-        PrimitiveType::I64 => Felt252::from(
+        PrimitiveType::I16 => Felt252::from(
             value
                 .as_i64()
-                .expect(format!("Error converting {value:?} to i64").as_str()),
+                .and_then(|v| i16::try_from(v).ok())
+                .ok_or_else(invalid)?,
         ),
-        PrimitiveType::BYTEARRAY => {
-            let mut p = Vec::new();
-            let bytes = value.as_str().unwrap().as_bytes();
-
-            let total_length = bytes.len().to_u32().unwrap() / 31;
-            p.push(Felt252::from(total_length));
-
-            bytes
-                .chunks(31)
-                .for_each(|v| p.push(Felt252::from_bytes_be_slice(v)));
-
-            let last_row_length = bytes.len().to_u32().unwrap() % 31;
-            if last_row_length == 0 {
-                p.push(Felt252::from(0));
-            }
-            p.push(Felt252::from(last_row_length));
-            return p;
-        }
-        PrimitiveType::BOOL => Felt252::from(
+        PrimitiveType::U32 => Felt252::from(
+            value
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(invalid)?,
+        ),
+        PrimitiveType::I32 => Felt252::from(
             value
-                .as_bool()
-                .expect(format!("Error converting {value} to bool").as_str()),
+                .as_i64()
+                .and_then(|v| i32::try_from(v).ok())
+                .ok_or_else(invalid)?,
+        ),
+        PrimitiveType::I64 => Felt252::from(value.as_i64().ok_or_else(invalid)?),
+        PrimitiveType::I128 => Felt252::from(
+            parse_integer(value)
+                .and_then(|v| i128::try_from(v).ok())
+                .ok_or_else(invalid)?,
         ),
+        PrimitiveType::BYTEARRAY => {
+            let bytes = value.as_str().ok_or_else(invalid)?.as_bytes();
+            return Ok(serialize_byte_array(bytes));
+        }
+        PrimitiveType::BYTES => {
+            let bytes = value.as_str().and_then(parse_bytes).ok_or_else(invalid)?;
+            return Ok(serialize_byte_array(&bytes));
+        }
+        PrimitiveType::BOOL => Felt252::from(value.as_bool().ok_or_else(invalid)?),
     };
-    vec![element]
+    Ok(vec![element])
 }
 
-fn deserialize_primitive(ty: &PrimitiveType, value: &mut &[Felt252]) -> Value {
-    let num = value[0].to_bigint();
-    *value = &value[1..];
+fn deserialize_primitive(
+    ty: &PrimitiveType,
+    value: &mut &[Felt252],
+    path: &str,
+) -> Result<Value, SerdeError> {
+    let expected = FieldType::Primitive(ty.clone());
+    let felt = next_felt(value, path, &expected)?;
+    let num = felt.to_bigint();
+    let invalid = || invalid_value(path, expected.clone(), felt);
 
     match ty {
         PrimitiveType::FELT252 => {
             let hex_string = format!("0x{}", num.to_str_radix(16));
-            json!(hex_string)
-        }
-        PrimitiveType::U64 => {
-            json!(u64::try_from(num).expect(format!("Error converting {value:?} to u64").as_str()))
-        }
-        PrimitiveType::U32 => {
-            json!(u32::try_from(num).expect(format!("Error converting {value:?} to u32").as_str()))
-        }
-        PrimitiveType::I32 => {
-            json!(i32::try_from(num).expect(format!("Error converting {value:?} to i32").as_str()))
+            Ok(json!(hex_string))
         }
-        PrimitiveType::I64 => {
-            json!(i64::try_from(num).expect(format!("Error converting {value:?} to i64").as_str()))
+        PrimitiveType::U8 => u8::try_from(num).map(|v| json!(v)).map_err(|_| invalid()),
+        PrimitiveType::U16 => u16::try_from(num).map(|v| json!(v)).map_err(|_| invalid()),
+        PrimitiveType::U64 => u64::try_from(num).map(|v| json!(v)).map_err(|_| invalid()),
+        PrimitiveType::U32 => u32::try_from(num).map(|v| json!(v)).map_err(|_| invalid()),
+        // 128 and 256-bit integers don't fit in a JSON number, so they travel as decimal strings.
+        PrimitiveType::U128 => u128::try_from(num)
+            .map(|v| json!(v.to_string()))
+            .map_err(|_| invalid()),
+        PrimitiveType::U256 => {
+            let low = u128::try_from(num).map_err(|_| invalid())?;
+            let high_felt = next_felt(value, path, &expected)?;
+            let high = high_felt
+                .to_u128()
+                .ok_or_else(|| invalid_value(path, expected.clone(), high_felt))?;
+            let v = (BigUint::from(high) << 128u32) + BigUint::from(low);
+            Ok(json!(v.to_string()))
         }
+        PrimitiveType::I8 => i8::try_from(felt_to_signed(&felt))
+            .map(|v| json!(v))
+            .map_err(|_| invalid()),
+        PrimitiveType::I16 => i16::try_from(felt_to_signed(&felt))
+            .map(|v| json!(v))
+            .map_err(|_| invalid()),
+        PrimitiveType::I32 => i32::try_from(felt_to_signed(&felt))
+            .map(|v| json!(v))
+            .map_err(|_| invalid()),
+        PrimitiveType::I64 => i64::try_from(felt_to_signed(&felt))
+            .map(|v| json!(v))
+            .map_err(|_| invalid()),
+        PrimitiveType::I128 => i128::try_from(felt_to_signed(&felt))
+            .map(|v| json!(v.to_string()))
+            .map_err(|_| invalid()),
         PrimitiveType::BYTEARRAY => {
-            let data_len = usize::try_from(num).unwrap();
-            let data = &value[0..data_len];
-            let pending_word = value[data_len];
-            let pending_word_len = value[data_len + 1].to_u32().unwrap() as usize;
-            let trim_len = 32 - pending_word_len;
-            *value = &value[(data_len + 2)..];
-
-            let mut v = Vec::<u8>::with_capacity(31 * data.len());
-            for felt in data {
-                v.extend_from_slice(&felt.to_bytes_be()[1..]);
-            }
-            v.extend_from_slice(&pending_word.to_bytes_be()[trim_len..]);
-
-            json!(String::from_utf8(v).unwrap())
+            let v = deserialize_byte_array(felt, value, path, &expected)?;
+            String::from_utf8(v)
+                .map(|s| json!(s))
+                .map_err(|err| invalid_value(path, expected, format!("{:?}", err.as_bytes())))
+        }
+        PrimitiveType::BYTES => {
+            let v = deserialize_byte_array(felt, value, path, &expected)?;
+            Ok(json!(BASE64_STANDARD.encode(v)))
         }
         PrimitiveType::BOOL => {
             if num.is_one() {
-                json!(true)
+                Ok(json!(true))
             } else if num.is_zero() {
-                json!(false)
+                Ok(json!(false))
             } else {
-                panic!("{value:#?} can't be converted to boolean")
+                Err(invalid())
             }
         }
     }
@@ -113,102 +339,225 @@ pub fn serialize_cairo_serde(
     config: &Configuration,
     ty: &FieldType,
     value: &Value,
-) -> Vec<Felt252> {
+) -> Result<Vec<Felt252>, SerdeError> {
+    match config.json_mapping {
+        JsonMapping::Cairo => serialize_field(config, ty, value, &root_path(ty)),
+        JsonMapping::Proto3 => {
+            let value = proto3::from_proto3(config, ty, value);
+            serialize_field(config, ty, &value, &root_path(ty))
+        }
+    }
+}
+
+fn serialize_field(
+    config: &Configuration,
+    ty: &FieldType,
+    value: &Value,
+    path: &str,
+) -> Result<Vec<Felt252>, SerdeError> {
     let mut result = Vec::new();
     match ty {
-        FieldType::Primitive(ty) => result.append(&mut serialize_primitive(ty, value)),
+        FieldType::Primitive(ty) => result.append(&mut serialize_primitive(ty, value, path)?),
         FieldType::Message(message_ty) => {
-            let message_config = config.messages.get(message_ty).expect(
-                format!("Key `{message_ty}` not found in configuration JSON file").as_str(),
-            );
+            let message_config = message_config(config, message_ty, path)?;
             let value = value
                 .as_object()
-                .expect(format!("must be an object to serialize as message {message_ty}").as_str());
+                .ok_or_else(|| invalid_value(path, ty.clone(), value))?;
             for field in message_config {
-                if let Some(field_value) = value.get(&field.name) {
-                    result.append(&mut serialize_cairo_serde(config, &field.ty, field_value));
-                } else if let Some(field_value) =
-                    value.get(field.name.trim_start_matches("felt252_"))
-                {
-                    // Try without the "felt252_" prefix
-                    result.append(&mut serialize_cairo_serde(config, &field.ty, field_value));
-                } else {
-                    panic!("Field {} not found in value object", field.name);
-                }
+                let field_path = format!("{path}.{}", field.name);
+                // Try without the "felt252_" prefix if the exact name is missing
+                let field_value = value
+                    .get(&field.name)
+                    .or_else(|| value.get(field.name.trim_start_matches("felt252_")))
+                    .ok_or_else(|| SerdeError::MissingField {
+                        path: field_path.clone(),
+                        expected: field.ty.clone(),
+                    })?;
+                result.append(&mut serialize_field(
+                    config,
+                    &field.ty,
+                    field_value,
+                    &field_path,
+                )?);
             }
         }
-        FieldType::Enum(_) => result.append(&mut serialize_primitive(&PrimitiveType::I32, value)),
+        FieldType::Enum(enum_ty) => {
+            // JSON carries the Protobuf enum number, Cairo Serde the variant index.
+            let mappings = enum_config(config, enum_ty, path)?;
+            let idx = value
+                .as_i64()
+                .and_then(|nb| mappings.iter().position(|m| i64::from(m.nb) == nb))
+                .ok_or_else(|| invalid_value(path, ty.clone(), value))?;
+            result.push(Felt252::from(idx));
+        }
         FieldType::Option(inner_ty) => {
             if value.is_null() {
-                result.append(&mut serialize_primitive(&PrimitiveType::U64, &json!(1)));
+                result.append(&mut serialize_primitive(
+                    &PrimitiveType::U64,
+                    &json!(1),
+                    path,
+                )?);
             } else {
-                result.append(&mut serialize_primitive(&PrimitiveType::U64, &json!(0)));
-                result.append(&mut serialize_cairo_serde(config, inner_ty, value));
+                result.append(&mut serialize_primitive(
+                    &PrimitiveType::U64,
+                    &json!(0),
+                    path,
+                )?);
+                result.append(&mut serialize_field(config, inner_ty, value, path)?);
             }
         }
         FieldType::Array(value_ty) => {
-            let value = value.as_array().expect("must be an array");
+            let value = value
+                .as_array()
+                .ok_or_else(|| invalid_value(path, ty.clone(), value))?;
             result.append(&mut serialize_primitive(
                 &PrimitiveType::U64,
                 &json!(value.len()),
-            ));
-            for element in value {
-                if let FieldType::Primitive(PrimitiveType::FELT252) = **value_ty {
-                    result.append(&mut serialize_primitive(&PrimitiveType::FELT252, element));
-                } else {
-                    result.append(&mut serialize_cairo_serde(config, value_ty, element));
-                }
+                path,
+            )?);
+            for (idx, element) in value.iter().enumerate() {
+                let element_path = format!("{path}[{idx}]");
+                result.append(&mut serialize_field(
+                    config,
+                    value_ty,
+                    element,
+                    &element_path,
+                )?);
+            }
+        }
+        FieldType::Map(key_field, value_field) => {
+            let value = value
+                .as_object()
+                .ok_or_else(|| invalid_value(path, ty.clone(), value))?;
+            result.push(Felt252::from(value.len()));
+            for (key, entry) in value {
+                let entry_path = format!("{path}[{key}]");
+                result.append(&mut serialize_field(
+                    config,
+                    &key_field.ty,
+                    &parse_map_key(key, &key_field.ty),
+                    &entry_path,
+                )?);
+                result.append(&mut serialize_field(
+                    config,
+                    &value_field.ty,
+                    entry,
+                    &entry_path,
+                )?);
             }
         }
+        FieldType::OneOf(variants) => {
+            // Exactly one of the oneof fields is set, e.g. `{"found": {...}}`.
+            let (idx, variant, variant_value) = value
+                .as_object()
+                .filter(|value| value.len() == 1)
+                .and_then(|value| value.iter().next())
+                .and_then(|(name, variant_value)| {
+                    variants
+                        .iter()
+                        .position(|variant| variant.name == *name)
+                        .map(|idx| (idx, &variants[idx], variant_value))
+                })
+                .ok_or_else(|| invalid_value(path, ty.clone(), value))?;
+            let variant_path = format!("{path}.{}", variant.name);
+            result.push(Felt252::from(idx));
+            result.append(&mut serialize_field(
+                config,
+                &variant.ty,
+                variant_value,
+                &variant_path,
+            )?);
+        }
     }
 
-    result
+    Ok(result)
 }
 
 pub fn deserialize_cairo_serde(
     config: &Configuration,
     ty: &FieldType,
     value: &mut &[Felt252],
-) -> Value {
+) -> Result<Value, SerdeError> {
+    let value = deserialize_field(config, ty, value, &root_path(ty))?;
+    match config.json_mapping {
+        JsonMapping::Cairo => Ok(value),
+        JsonMapping::Proto3 => Ok(proto3::to_proto3(config, ty, value)),
+    }
+}
+
+fn deserialize_field(
+    config: &Configuration,
+    ty: &FieldType,
+    value: &mut &[Felt252],
+    path: &str,
+) -> Result<Value, SerdeError> {
     match ty {
-        FieldType::Primitive(ty) => deserialize_primitive(ty, value),
+        FieldType::Primitive(ty) => deserialize_primitive(ty, value, path),
         FieldType::Message(message_ty) => {
-            let message_config = config.messages.get(message_ty).expect(
-                format!("Key `{message_ty}` not found in configuration JSON file").as_str(),
-            );
+            let message_config = message_config(config, message_ty, path)?;
             let mut result = Map::new();
             for field in message_config {
+                let field_path = format!("{path}.{}", field.name);
                 result.insert(
                     field.name.clone(),
-                    deserialize_cairo_serde(config, &field.ty, value),
+                    deserialize_field(config, &field.ty, value, &field_path)?,
                 );
             }
-            Value::Object(result)
+            Ok(Value::Object(result))
+        }
+        FieldType::Enum(enum_ty) => {
+            let mappings = enum_config(config, enum_ty, path)?;
+            let idx = next_felt(value, path, ty)?;
+            idx.to_usize()
+                .and_then(|idx| mappings.get(idx))
+                .map(|m| json!(m.nb))
+                .ok_or_else(|| invalid_value(path, ty.clone(), idx))
         }
-        FieldType::Enum(_) => deserialize_primitive(&PrimitiveType::I32, value),
         FieldType::Option(inner_ty) => {
-            let idx = deserialize_primitive(&PrimitiveType::U64, value);
-            if idx == 0 {
-                deserialize_cairo_serde(config, inner_ty, value)
+            let idx = next_felt(value, path, ty)?;
+            if idx.is_zero() {
+                deserialize_field(config, inner_ty, value, path)
+            } else if idx.is_one() {
+                Ok(Value::Null)
             } else {
-                Value::Null
+                Err(invalid_value(path, ty.clone(), idx))
             }
         }
         FieldType::Array(value_ty) => {
-            let len = deserialize_primitive(&PrimitiveType::U64, value)
-                .as_number()
-                .unwrap()
-                .as_u64()
-                .unwrap() as usize;
+            let len = next_felt(value, path, ty)?;
+            let len = len
+                .to_usize()
+                .ok_or_else(|| invalid_value(path, ty.clone(), len))?;
             let mut result = Vec::new();
-            for _ in 0..len {
-                if let FieldType::Primitive(PrimitiveType::FELT252) = **value_ty {
-                    result.push(deserialize_primitive(&PrimitiveType::FELT252, value));
-                } else {
-                    result.push(deserialize_cairo_serde(config, value_ty, value));
-                }
+            for idx in 0..len {
+                let element_path = format!("{path}[{idx}]");
+                result.push(deserialize_field(config, value_ty, value, &element_path)?);
+            }
+            Ok(Value::Array(result))
+        }
+        FieldType::Map(key_field, value_field) => {
+            let len = next_felt(value, path, ty)?;
+            let len = len
+                .to_usize()
+                .ok_or_else(|| invalid_value(path, ty.clone(), len))?;
+            let mut result = Map::new();
+            for idx in 0..len {
+                let entry_path = format!("{path}[{idx}]");
+                let key = deserialize_field(config, &key_field.ty, value, &entry_path)?;
+                let entry = deserialize_field(config, &value_field.ty, value, &entry_path)?;
+                result.insert(format_map_key(key), entry);
             }
-            Value::Array(result)
+            Ok(Value::Object(result))
+        }
+        FieldType::OneOf(variants) => {
+            let idx = next_felt(value, path, ty)?;
+            let variant = idx
+                .to_usize()
+                .and_then(|idx| variants.get(idx))
+                .ok_or_else(|| invalid_value(path, ty.clone(), idx))?;
+            let variant_path = format!("{path}.{}", variant.name);
+            let variant_value = deserialize_field(config, &variant.ty, value, &variant_path)?;
+            Ok(json!({ variant.name.clone(): variant_value }))
         }
     }
 }
@@ -216,9 +565,15 @@ pub fn deserialize_cairo_serde(
 #[cfg(test)]
 mod tests {
     use crate::configuration::{
-        Configuration, Field, FieldType, MethodDeclaration, PrimitiveType, Service,
+        Configuration, Field, FieldType, IntegerEncoding, JsonMapping, Mapping, MethodDeclaration,
+        PrimitiveType, Service, Tag,
     };
-    use crate::{deserialize_cairo_serde, serialize_cairo_serde};
+    use crate::{
+        decode_protobuf, deserialize_cairo_serde, encode_protobuf, serialize_byte_array,
+        serialize_cairo_serde, SerdeError,
+    };
+    use base64::prelude::*;
+    use prost::Message;
     use serde_json::{json, Value};
     use starknet_types_core::felt::Felt as Felt252;
     use std::collections::{BTreeMap, HashMap};
@@ -232,7 +587,8 @@ mod tests {
             &configuration,
             &FieldType::Message(message_type.into()),
             &json,
-        );
+        )
+        .unwrap();
 
         println!("configuration {configuration:?}");
         println!("result {cairo_message:?}");
@@ -252,7 +608,8 @@ mod tests {
             &configuration,
             &FieldType::Message(message_type.into()),
             &mut cairo_message.as_ref(),
-        );
+        )
+        .unwrap();
         let expected_json = json!({
             "n": 42 * 42,
             "x": Value::Null,
@@ -279,12 +636,509 @@ mod tests {
             "c": "dolor"
         });
         let ty = FieldType::Message("Strings".into());
-        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json);
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
         let deserialized_message =
-            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref());
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
         assert_eq!(json, deserialized_message);
     }
 
+    #[test]
+    fn it_reports_missing_fields() {
+        let configuration = test_configuration();
+        let json = json!({ "n": 42, "x": { "inner": 1 } });
+        let err =
+            serialize_cairo_serde(&configuration, &FieldType::Message("Request".into()), &json)
+                .unwrap_err();
+
+        assert_eq!(
+            err,
+            SerdeError::MissingField {
+                path: "Request.y".into(),
+                expected: FieldType::Array(Box::new(FieldType::Primitive(PrimitiveType::I32))),
+            }
+        );
+    }
+
+    #[test]
+    fn it_reports_invalid_values() {
+        let configuration = test_configuration();
+        let json = json!({ "n": 42, "x": { "inner": -1 }, "y": [] });
+        let err =
+            serialize_cairo_serde(&configuration, &FieldType::Message("Request".into()), &json)
+                .unwrap_err();
+
+        assert_eq!(
+            err,
+            SerdeError::InvalidValue {
+                path: "Request.x.inner".into(),
+                expected: FieldType::Primitive(PrimitiveType::U32),
+                value: "-1".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn it_reports_truncated_input() {
+        let configuration = test_configuration();
+        let cairo_message = vec![Felt252::from(42), Felt252::from(1), Felt252::from(2)];
+        let err = deserialize_cairo_serde(
+            &configuration,
+            &FieldType::Message("Request".into()),
+            &mut cairo_message.as_ref(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            SerdeError::UnexpectedEnd {
+                path: "Request.y[0]".into(),
+                expected: FieldType::Primitive(PrimitiveType::I32),
+            }
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_utf8() {
+        let configuration = test_configuration();
+        let cairo_message = vec![Felt252::from(0), Felt252::from(0xff), Felt252::from(1)];
+        let err = deserialize_cairo_serde(
+            &configuration,
+            &FieldType::Primitive(PrimitiveType::BYTEARRAY),
+            &mut cairo_message.as_ref(),
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_handles_wide_integers() {
+        let configuration = test_configuration();
+        let json = json!({
+            "a": 255,
+            "b": u128::MAX.to_string(),
+            "c": "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+        });
+        let ty = FieldType::Message("Wide".into());
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(
+            cairo_message,
+            vec![
+                Felt252::from(255),
+                Felt252::from(u128::MAX),
+                Felt252::from(u128::MAX),
+                Felt252::from(u128::MAX),
+            ]
+        );
+
+        let deserialized_message =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized_message);
+    }
+
+    #[test]
+    fn it_splits_u256_into_low_and_high() {
+        let configuration = test_configuration();
+        let ty = FieldType::Primitive(PrimitiveType::U256);
+        let cairo_message = serialize_cairo_serde(
+            &configuration,
+            &ty,
+            &json!("0x100000000000000000000000000000002"),
+        )
+        .unwrap();
+        assert_eq!(cairo_message, vec![Felt252::from(2), Felt252::from(1)]);
+
+        let err = serialize_cairo_serde(&configuration, &ty, &json!(-1)).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_round_trips_signed_integers() {
+        let configuration = test_configuration();
+        let cases = [
+            (PrimitiveType::I8, json!([i8::MIN, -1, 0, i8::MAX])),
+            (PrimitiveType::I16, json!([i16::MIN, -1, 0, i16::MAX])),
+            (PrimitiveType::I32, json!([i32::MIN, -1, 0, i32::MAX])),
+            (PrimitiveType::I64, json!([i64::MIN, -1, 0, i64::MAX])),
+            (
+                PrimitiveType::I128,
+                json!([i128::MIN.to_string(), "-1", "0", i128::MAX.to_string()]),
+            ),
+        ];
+        for (ty, values) in cases {
+            let ty = FieldType::Array(Box::new(FieldType::Primitive(ty)));
+            let cairo_message = serialize_cairo_serde(&configuration, &ty, &values).unwrap();
+            let deserialized =
+                deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+            assert_eq!(values, deserialized, "{ty:?}");
+        }
+
+        assert_eq!(
+            serialize_cairo_serde(
+                &configuration,
+                &FieldType::Primitive(PrimitiveType::I32),
+                &json!(-1)
+            )
+            .unwrap(),
+            vec![Felt252::from(-1)]
+        );
+    }
+
+    #[test]
+    fn it_range_checks_signed_integers() {
+        let configuration = test_configuration();
+        let ty = FieldType::Primitive(PrimitiveType::I32);
+        for felt in [
+            Felt252::from(i32::MAX as i64 + 1),
+            Felt252::from(i32::MIN as i64 - 1),
+        ] {
+            let err =
+                deserialize_cairo_serde(&configuration, &ty, &mut [felt].as_ref()).unwrap_err();
+            assert!(matches!(err, SerdeError::InvalidValue { .. }));
+        }
+
+        let err =
+            serialize_cairo_serde(&configuration, &ty, &json!(i32::MIN as i64 - 1)).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_translates_enum_numbers_to_variant_indices() {
+        let configuration = test_configuration();
+        let ty = FieldType::Array(Box::new(FieldType::Enum("Color".into())));
+        let json = json!([7, 0, 3]);
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(
+            cairo_message,
+            vec![
+                Felt252::from(3),
+                Felt252::from(2),
+                Felt252::from(0),
+                Felt252::from(1)
+            ]
+        );
+
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized);
+    }
+
+    #[test]
+    fn it_rejects_unknown_enum_values() {
+        let configuration = test_configuration();
+        let ty = FieldType::Enum("Color".into());
+
+        let err = serialize_cairo_serde(&configuration, &ty, &json!(1)).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+
+        let err = deserialize_cairo_serde(&configuration, &ty, &mut [Felt252::from(3)].as_ref())
+            .unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_handles_maps() {
+        let configuration = test_configuration();
+        let ty = FieldType::Map(
+            Box::new(Field::new("key", FieldType::Primitive(PrimitiveType::U32))),
+            Box::new(Field::new(
+                "value",
+                FieldType::Primitive(PrimitiveType::BYTEARRAY),
+            )),
+        );
+        let json = json!({ "1": "one", "2": "two" });
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(cairo_message[0..2], [Felt252::from(2), Felt252::from(1)]);
+
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized);
+
+        let err = serialize_cairo_serde(&configuration, &ty, &json!({ "x": "one" })).unwrap_err();
+        assert_eq!(
+            err,
+            SerdeError::InvalidValue {
+                path: "value[x]".into(),
+                expected: FieldType::Primitive(PrimitiveType::U32),
+                value: "\"x\"".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn it_handles_oneofs() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("Lookup".into());
+        let cases = [
+            (
+                json!({ "result": { "found": { "n": 7 } } }),
+                vec![Felt252::from(0), Felt252::from(0), Felt252::from(7)],
+            ),
+            (
+                json!({ "result": { "error": "" } }),
+                vec![
+                    Felt252::from(0),
+                    Felt252::from(1),
+                    Felt252::from(0),
+                    Felt252::from(0),
+                    Felt252::from(0),
+                ],
+            ),
+            (json!({ "result": null }), vec![Felt252::from(1)]),
+        ];
+        for (json, expected) in cases {
+            let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+            assert_eq!(cairo_message, expected);
+
+            let deserialized =
+                deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+            assert_eq!(json, deserialized);
+        }
+
+        let json = json!({ "result": { "found": { "n": 7 }, "error": "" } });
+        let err = serialize_cairo_serde(&configuration, &ty, &json).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_handles_recursive_messages() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("Node".into());
+        let json = json!({
+            "value": 1,
+            "left": { "value": 2, "left": null, "right": null },
+            "right": null
+        });
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(
+            cairo_message,
+            vec![
+                Felt252::from(1),
+                Felt252::from(0),
+                Felt252::from(2),
+                Felt252::from(1),
+                Felt252::from(1),
+                Felt252::from(1),
+            ]
+        );
+
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized);
+    }
+
+    #[test]
+    fn it_handles_arbitrary_bytes() {
+        let configuration = test_configuration();
+        let ty = FieldType::Primitive(PrimitiveType::BYTES);
+        let bytes = (0..=255u8).rev().collect::<Vec<_>>();
+
+        let json = json!(BASE64_STANDARD.encode(&bytes));
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(cairo_message, serialize_byte_array(&bytes));
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized);
+
+        // Base64 strings may start with `0x`, and aren't read as hex.
+        let bytes = [0xd3, 0x1f, 0xff];
+        let json = json!("0x//");
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(cairo_message, serialize_byte_array(&bytes));
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(deserialized, json);
+
+        let err = serialize_cairo_serde(&configuration, &ty, &json!("not base64!")).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidValue { .. }));
+    }
+
+    #[test]
+    fn it_handles_proto3_json() {
+        let configuration = Configuration {
+            json_mapping: JsonMapping::Proto3,
+            ..test_configuration()
+        };
+        let ty = FieldType::Message("Proto3".into());
+        let json = json!({
+            "someNumber": "18446744073709551615",
+            "color": "COLOR_RED",
+            "colors": ["COLOR_GREEN", "COLOR_UNSPECIFIED"],
+            "payload": "/wD+",
+            "found": { "n": "7" }
+        });
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(json, deserialized);
+
+        // Snake case names, numbers and enum numbers are accepted as well.
+        let json = json!({
+            "some_number": 18446744073709551615u64,
+            "color": 3,
+            "colors": [7, "COLOR_UNSPECIFIED"],
+            "payload": "/wD+",
+            "found": { "n": 7 }
+        });
+        let snake_case_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+        assert_eq!(cairo_message, snake_case_message);
+    }
+
+    #[test]
+    fn it_defaults_missing_proto3_fields() {
+        let configuration = Configuration {
+            json_mapping: JsonMapping::Proto3,
+            ..test_configuration()
+        };
+        let ty = FieldType::Message("Proto3".into());
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json!({})).unwrap();
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(
+            deserialized,
+            json!({
+                "someNumber": "0",
+                "color": "COLOR_UNSPECIFIED",
+                "colors": [],
+                "payload": ""
+            })
+        );
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct WireResponse {
+        #[prost(uint64, tag = "1")]
+        n: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Wire {
+        #[prost(uint64, tag = "1")]
+        n: u64,
+        #[prost(sint32, tag = "2")]
+        delta: i32,
+        #[prost(fixed32, repeated, tag = "3")]
+        ids: Vec<u32>,
+        #[prost(string, tag = "4")]
+        name: String,
+        #[prost(bytes = "vec", tag = "5")]
+        payload: Vec<u8>,
+        #[prost(int32, tag = "6")]
+        color: i32,
+        #[prost(message, optional, tag = "7")]
+        response: Option<WireResponse>,
+        #[prost(map = "string, int64", tag = "8")]
+        balances: HashMap<String, i64>,
+    }
+
+    #[test]
+    fn it_encodes_protobuf() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("Wire".into());
+        let json = json!({
+            "n": 300,
+            "delta": -2,
+            "ids": [1, 4294967295u32],
+            "name": "lorem",
+            "payload": "/wD+",
+            "color": 7,
+            "response": { "n": 42 },
+            "balances": { "alice": -5 }
+        });
+        let message = Wire {
+            n: 300,
+            delta: -2,
+            ids: vec![1, u32::MAX],
+            name: "lorem".into(),
+            payload: vec![0xff, 0x00, 0xfe],
+            color: 7,
+            response: Some(WireResponse { n: 42 }),
+            balances: HashMap::from([("alice".into(), -5)]),
+        };
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+
+        let bytes = encode_protobuf(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(Wire::decode(bytes.as_ref()).unwrap(), message);
+
+        let decoded = decode_protobuf(&configuration, &ty, &message.encode_to_vec()).unwrap();
+        assert_eq!(decoded, cairo_message);
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct WireMaps {
+        #[prost(map = "sint32, sfixed32", tag = "1")]
+        zigzag_to_fixed: HashMap<i32, i32>,
+        #[prost(map = "fixed64, sint64", tag = "2")]
+        fixed_to_zigzag: HashMap<u64, i64>,
+    }
+
+    #[test]
+    fn it_encodes_map_entries_with_their_encoding() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("WireMaps".into());
+        let json = json!({
+            "zigzag_to_fixed": { "-3": -7, "5": 9 },
+            "fixed_to_zigzag": { "18446744073709551615": -1 }
+        });
+        let message = WireMaps {
+            zigzag_to_fixed: HashMap::from([(-3, -7), (5, 9)]),
+            fixed_to_zigzag: HashMap::from([(u64::MAX, -1)]),
+        };
+        let cairo_message = serialize_cairo_serde(&configuration, &ty, &json).unwrap();
+
+        let bytes = encode_protobuf(&configuration, &ty, &mut cairo_message.as_ref()).unwrap();
+        assert_eq!(WireMaps::decode(bytes.as_ref()).unwrap(), message);
+
+        let decoded = decode_protobuf(&configuration, &ty, &message.encode_to_vec()).unwrap();
+        assert_eq!(decoded, cairo_message);
+    }
+
+    #[test]
+    fn it_rejects_out_of_range_protobuf_integers() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("Inner".into());
+        // Field 1 as a varint holding 2^32, one more than the largest `uint32`.
+        let bytes = [0x08, 0x80, 0x80, 0x80, 0x80, 0x10];
+        let err = decode_protobuf(&configuration, &ty, &bytes).unwrap_err();
+        assert_eq!(
+            err,
+            SerdeError::InvalidValue {
+                path: "Inner.inner".into(),
+                expected: FieldType::Primitive(PrimitiveType::U32),
+                value: "4294967296".into(),
+            }
+        );
+
+        let bytes = [0x08, 0xff, 0xff, 0xff, 0xff, 0x0f];
+        let decoded = decode_protobuf(&configuration, &ty, &bytes).unwrap();
+        assert_eq!(decoded, vec![Felt252::from(u32::MAX)]);
+    }
+
+    #[test]
+    fn it_decodes_missing_protobuf_fields_as_defaults() {
+        let configuration = test_configuration();
+        let ty = FieldType::Message("Wire".into());
+        let decoded = decode_protobuf(&configuration, &ty, &[]).unwrap();
+        let deserialized =
+            deserialize_cairo_serde(&configuration, &ty, &mut decoded.as_ref()).unwrap();
+        assert_eq!(
+            deserialized,
+            json!({
+                "n": 0,
+                "delta": 0,
+                "ids": [],
+                "name": "",
+                "payload": "",
+                "color": 0,
+                "response": null,
+                "balances": {}
+            })
+        );
+
+        let err = decode_protobuf(&configuration, &ty, &[0x0a, 0x05, 0x01]).unwrap_err();
+        assert!(matches!(err, SerdeError::InvalidProtobuf { .. }));
+    }
+
     fn test_configuration() -> Configuration {
         let mut messages = BTreeMap::new();
         messages.insert(
@@ -292,47 +1146,215 @@ mod tests {
             vec![Field {
                 name: "inner".into(),
                 ty: FieldType::Primitive(PrimitiveType::U32),
+                tag: Some(Tag {
+                    number: 1,
+                    encoding: IntegerEncoding::Varint,
+                }),
             }],
         );
         messages.insert(
             String::from("Request"),
+            vec![
+                Field::new("n", FieldType::Primitive(PrimitiveType::U64)),
+                Field::new(
+                    "x",
+                    FieldType::Option(Box::new(FieldType::Message("Inner".into()))),
+                ),
+                Field::new(
+                    "y",
+                    FieldType::Array(Box::new(FieldType::Primitive(PrimitiveType::I32))),
+                ),
+            ],
+        );
+        messages.insert(
+            String::from("Strings"),
+            vec![
+                Field::new("a", FieldType::Primitive(PrimitiveType::BYTEARRAY)),
+                Field::new("b", FieldType::Primitive(PrimitiveType::BYTEARRAY)),
+                Field::new("c", FieldType::Primitive(PrimitiveType::BYTEARRAY)),
+            ],
+        );
+        messages.insert(
+            String::from("Wide"),
+            vec![
+                Field::new("a", FieldType::Primitive(PrimitiveType::U8)),
+                Field::new("b", FieldType::Primitive(PrimitiveType::U128)),
+                Field::new("c", FieldType::Primitive(PrimitiveType::U256)),
+            ],
+        );
+        messages.insert(
+            String::from("Node"),
+            vec![
+                Field::new("value", FieldType::Primitive(PrimitiveType::U64)),
+                Field::new(
+                    "left",
+                    FieldType::Option(Box::new(FieldType::Message("Node".into()))),
+                ),
+                Field::new(
+                    "right",
+                    FieldType::Option(Box::new(FieldType::Message("Node".into()))),
+                ),
+            ],
+        );
+        messages.insert(
+            String::from("Wire"),
             vec![
                 Field {
                     name: "n".into(),
                     ty: FieldType::Primitive(PrimitiveType::U64),
+                    tag: Some(Tag {
+                        number: 1,
+                        encoding: IntegerEncoding::Varint,
+                    }),
                 },
                 Field {
-                    name: "x".into(),
-                    ty: FieldType::Option(Box::new(FieldType::Message("Inner".into()))),
+                    name: "delta".into(),
+                    ty: FieldType::Primitive(PrimitiveType::I32),
+                    tag: Some(Tag {
+                        number: 2,
+                        encoding: IntegerEncoding::ZigZag,
+                    }),
                 },
                 Field {
-                    name: "y".into(),
-                    ty: FieldType::Array(Box::new(FieldType::Primitive(PrimitiveType::I32))),
+                    name: "ids".into(),
+                    ty: FieldType::Array(Box::new(FieldType::Primitive(PrimitiveType::U32))),
+                    tag: Some(Tag {
+                        number: 3,
+                        encoding: IntegerEncoding::Fixed,
+                    }),
                 },
-            ],
-        );
-        messages.insert(
-            String::from("Strings"),
-            vec![
                 Field {
-                    name: "a".into(),
+                    name: "name".into(),
                     ty: FieldType::Primitive(PrimitiveType::BYTEARRAY),
+                    tag: Some(Tag {
+                        number: 4,
+                        encoding: IntegerEncoding::Varint,
+                    }),
                 },
                 Field {
-                    name: "b".into(),
-                    ty: FieldType::Primitive(PrimitiveType::BYTEARRAY),
+                    name: "payload".into(),
+                    ty: FieldType::Primitive(PrimitiveType::BYTES),
+                    tag: Some(Tag {
+                        number: 5,
+                        encoding: IntegerEncoding::Varint,
+                    }),
                 },
                 Field {
-                    name: "c".into(),
-                    ty: FieldType::Primitive(PrimitiveType::BYTEARRAY),
+                    name: "color".into(),
+                    ty: FieldType::Enum("Color".into()),
+                    tag: Some(Tag {
+                        number: 6,
+                        encoding: IntegerEncoding::Varint,
+                    }),
+                },
+                Field {
+                    name: "response".into(),
+                    ty: FieldType::Option(Box::new(FieldType::Message("Response".into()))),
+                    tag: Some(Tag {
+                        number: 7,
+                        encoding: IntegerEncoding::Varint,
+                    }),
                 },
+                Field {
+                    name: "balances".into(),
+                    ty: FieldType::Map(
+                        Box::new(Field {
+                            name: "key".into(),
+                            ty: FieldType::Primitive(PrimitiveType::BYTEARRAY),
+                            tag: Some(Tag {
+                                number: 1,
+                                encoding: IntegerEncoding::Varint,
+                            }),
+                        }),
+                        Box::new(Field {
+                            name: "value".into(),
+                            ty: FieldType::Primitive(PrimitiveType::I64),
+                            tag: Some(Tag {
+                                number: 2,
+                                encoding: IntegerEncoding::Varint,
+                            }),
+                        }),
+                    ),
+                    tag: Some(Tag {
+                        number: 8,
+                        encoding: IntegerEncoding::Varint,
+                    }),
+                },
+            ],
+        );
+        let map_field = |name: &str, number, key: (PrimitiveType, _), value: (PrimitiveType, _)| {
+            let entry_field = |name: &str, number, (ty, encoding)| Field {
+                name: name.into(),
+                ty: FieldType::Primitive(ty),
+                tag: Some(Tag { number, encoding }),
+            };
+            Field {
+                name: name.into(),
+                ty: FieldType::Map(
+                    Box::new(entry_field("key", 1, key)),
+                    Box::new(entry_field("value", 2, value)),
+                ),
+                tag: Some(Tag {
+                    number,
+                    encoding: IntegerEncoding::Varint,
+                }),
+            }
+        };
+        messages.insert(
+            String::from("WireMaps"),
+            vec![
+                map_field(
+                    "zigzag_to_fixed",
+                    1,
+                    (PrimitiveType::I32, IntegerEncoding::ZigZag),
+                    (PrimitiveType::I32, IntegerEncoding::Fixed),
+                ),
+                map_field(
+                    "fixed_to_zigzag",
+                    2,
+                    (PrimitiveType::U64, IntegerEncoding::Fixed),
+                    (PrimitiveType::I64, IntegerEncoding::ZigZag),
+                ),
+            ],
+        );
+        messages.insert(
+            String::from("Proto3"),
+            vec![
+                Field::new("some_number", FieldType::Primitive(PrimitiveType::U64)),
+                Field::new("color", FieldType::Enum("Color".into())),
+                Field::new(
+                    "colors",
+                    FieldType::Array(Box::new(FieldType::Enum("Color".into()))),
+                ),
+                Field::new("payload", FieldType::Primitive(PrimitiveType::BYTES)),
+                Field::new(
+                    "result",
+                    FieldType::Option(Box::new(FieldType::OneOf(vec![
+                        Field::new("found", FieldType::Message("Response".into())),
+                        Field::new("error", FieldType::Primitive(PrimitiveType::BYTEARRAY)),
+                    ]))),
+                ),
             ],
         );
+        messages.insert(
+            String::from("Lookup"),
+            vec![Field::new(
+                "result",
+                FieldType::Option(Box::new(FieldType::OneOf(vec![
+                    Field::new("found", FieldType::Message("Response".into())),
+                    Field::new("error", FieldType::Primitive(PrimitiveType::BYTEARRAY)),
+                ]))),
+            )],
+        );
         messages.insert(
             String::from("Response"),
             vec![Field {
                 name: "n".into(),
                 ty: FieldType::Primitive(PrimitiveType::U64),
+                tag: Some(Tag {
+                    number: 1,
+                    encoding: IntegerEncoding::Varint,
+                }),
             }],
         );
 
@@ -342,17 +1364,40 @@ mod tests {
             MethodDeclaration {
                 input: FieldType::Message("Request".into()),
                 output: FieldType::Message("Response".into()),
+                grpc_path: Some("/oracle.SqrtOracle/Sqrt".into()),
+                fallible: false,
             },
         );
 
         let mut services = BTreeMap::new();
         services.insert(String::from("SqrtOracle"), Service { methods });
 
-        let enums = BTreeMap::new();
+        let mut enums = BTreeMap::new();
+        enums.insert(
+            String::from("Color"),
+            vec![
+                Mapping {
+                    name: "Unspecified".into(),
+                    nb: 0,
+                    proto_name: Some("COLOR_UNSPECIFIED".into()),
+                },
+                Mapping {
+                    name: "Red".into(),
+                    nb: 3,
+                    proto_name: Some("COLOR_RED".into()),
+                },
+                Mapping {
+                    name: "Green".into(),
+                    nb: 7,
+                    proto_name: Some("COLOR_GREEN".into()),
+                },
+            ],
+        );
         Configuration {
             enums,
             messages,
             services,
+            ..Default::default()
         }
     }
 }
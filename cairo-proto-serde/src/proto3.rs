@@ -0,0 +1,262 @@
+//! Conversion between the canonical proto3 JSON mapping and the JSON the Cairo Serde
+//! (de)serializer works with.
+//!
+//! Conversions are lenient: values that can't be converted are passed through unchanged, so that
+//! the (de)serializer reports them with the path of the offending field.
+
+use num_bigint::BigInt;
+use serde_json::{Map, Value};
+
+use crate::configuration::{Configuration, Field, FieldType, PrimitiveType};
+use crate::parse_integer;
+
+/// Converts a field name to lowerCamelCase, the way `protoc` derives the JSON name of a field.
+fn json_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            result.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Looks a field up by its JSON name, falling back to its name in the `.proto` file, as proto3
+/// JSON parsers do.
+fn get_field<'a>(value: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    value
+        .get(&json_name(name))
+        .or_else(|| value.get(name))
+        .or_else(|| value.get(name.trim_start_matches("felt252_")))
+        .filter(|value| !value.is_null())
+}
+
+/// Converts a number that fits in 64 bits to a JSON number.
+fn integer_to_number(value: BigInt) -> Option<Value> {
+    i64::try_from(&value)
+        .map(Value::from)
+        .or_else(|_| u64::try_from(&value).map(Value::from))
+        .ok()
+}
+
+/// Value of a field omitted from a proto3 JSON object.
+pub(crate) fn default_value(config: &Configuration, ty: &FieldType) -> Value {
+    match ty {
+        FieldType::Primitive(PrimitiveType::U128 | PrimitiveType::U256 | PrimitiveType::I128) => {
+            Value::from("0")
+        }
+        FieldType::Primitive(PrimitiveType::BOOL) => Value::from(false),
+        FieldType::Primitive(PrimitiveType::BYTEARRAY | PrimitiveType::BYTES) => Value::from(""),
+        FieldType::Primitive(PrimitiveType::FELT252) => Value::from("0x0"),
+        FieldType::Primitive(_) | FieldType::Enum(_) => Value::from(0),
+        FieldType::Message(_) => from_proto3(config, ty, &Value::Object(Map::new())),
+        FieldType::Option(_) | FieldType::OneOf(_) => Value::Null,
+        FieldType::Array(_) => Value::Array(Vec::new()),
+        FieldType::Map(_, _) => Value::Object(Map::new()),
+    }
+}
+
+/// Converts a value in the proto3 JSON mapping to the JSON expected by the serializer.
+pub(crate) fn from_proto3(config: &Configuration, ty: &FieldType, value: &Value) -> Value {
+    if value.is_null() {
+        return default_value(config, ty);
+    }
+
+    match ty {
+        FieldType::Primitive(
+            PrimitiveType::U8
+            | PrimitiveType::U16
+            | PrimitiveType::U32
+            | PrimitiveType::U64
+            | PrimitiveType::I8
+            | PrimitiveType::I16
+            | PrimitiveType::I32
+            | PrimitiveType::I64,
+        ) => parse_integer(value)
+            .and_then(integer_to_number)
+            .unwrap_or_else(|| value.clone()),
+        FieldType::Primitive(_) => value.clone(),
+        FieldType::Enum(enum_ty) => {
+            let Some(name) = value.as_str() else {
+                return value.clone();
+            };
+            config
+                .enums
+                .get(enum_ty)
+                .and_then(|mappings| {
+                    mappings
+                        .iter()
+                        .find(|m| m.proto_name.as_deref() == Some(name) || m.name == name)
+                })
+                .map(|m| Value::from(m.nb))
+                .unwrap_or_else(|| value.clone())
+        }
+        FieldType::Message(message_ty) => {
+            let (Some(fields), Some(value)) = (config.messages.get(message_ty), value.as_object())
+            else {
+                return value.clone();
+            };
+            let mut result = Map::new();
+            for field in fields {
+                let field_value = match &field.ty {
+                    // Fields of a oneof are inlined in the message, at most one of them is set.
+                    FieldType::Option(inner_ty) => match inner_ty.as_ref() {
+                        FieldType::OneOf(variants) => from_proto3_oneof(config, variants, value),
+                        _ => get_field(value, &field.name)
+                            .map(|value| from_proto3(config, &field.ty, value))
+                            .unwrap_or(Value::Null),
+                    },
+                    _ => get_field(value, &field.name)
+                        .map(|value| from_proto3(config, &field.ty, value))
+                        .unwrap_or_else(|| default_value(config, &field.ty)),
+                };
+                result.insert(field.name.clone(), field_value);
+            }
+            Value::Object(result)
+        }
+        FieldType::Option(inner_ty) => from_proto3(config, inner_ty, value),
+        FieldType::Array(value_ty) => match value.as_array() {
+            Some(value) => value
+                .iter()
+                .map(|element| from_proto3(config, value_ty, element))
+                .collect(),
+            None => value.clone(),
+        },
+        FieldType::Map(_, value_field) => match value.as_object() {
+            Some(value) => value
+                .iter()
+                .map(|(key, entry)| (key.clone(), from_proto3(config, &value_field.ty, entry)))
+                .collect(),
+            None => value.clone(),
+        },
+        FieldType::OneOf(variants) => match value.as_object() {
+            Some(value) => from_proto3_oneof(config, variants, value),
+            None => value.clone(),
+        },
+    }
+}
+
+/// Collects the oneof fields set in a proto3 JSON object into `{"variant": value}`.
+fn from_proto3_oneof(
+    config: &Configuration,
+    variants: &[Field],
+    value: &Map<String, Value>,
+) -> Value {
+    let set = variants
+        .iter()
+        .filter_map(|variant| {
+            get_field(value, &variant.name).map(|value| {
+                (
+                    variant.name.clone(),
+                    from_proto3(config, &variant.ty, value),
+                )
+            })
+        })
+        .collect::<Map<_, _>>();
+    // More than one field set is left for the serializer to reject.
+    if set.is_empty() {
+        Value::Null
+    } else {
+        Value::Object(set)
+    }
+}
+
+/// Converts a value produced by the deserializer to the proto3 JSON mapping.
+pub(crate) fn to_proto3(config: &Configuration, ty: &FieldType, value: Value) -> Value {
+    match ty {
+        // 64-bit integers don't fit in a double, so proto3 JSON carries them as strings.
+        FieldType::Primitive(PrimitiveType::U64 | PrimitiveType::I64) => match value {
+            Value::Number(n) => Value::from(n.to_string()),
+            value => value,
+        },
+        FieldType::Primitive(_) => value,
+        FieldType::Enum(enum_ty) => config
+            .enums
+            .get(enum_ty)
+            .zip(value.as_i64())
+            .and_then(|(mappings, nb)| mappings.iter().find(|m| i64::from(m.nb) == nb))
+            .map(|m| Value::from(m.proto_name.clone().unwrap_or_else(|| m.name.clone())))
+            .unwrap_or(value),
+        FieldType::Message(message_ty) => {
+            let Some(fields) = config.messages.get(message_ty) else {
+                return value;
+            };
+            let Value::Object(mut value) = value else {
+                return value;
+            };
+            let mut result = Map::new();
+            for field in fields {
+                let field_value = value.remove(&field.name).unwrap_or(Value::Null);
+                match (&field.ty, field_value) {
+                    (FieldType::Option(inner_ty), Value::Object(set))
+                        if matches!(inner_ty.as_ref(), FieldType::OneOf(_)) =>
+                    {
+                        if let Value::Object(set) = to_proto3(config, inner_ty, Value::Object(set))
+                        {
+                            result.extend(set);
+                        }
+                    }
+                    (FieldType::Option(inner_ty), Value::Null)
+                        if matches!(inner_ty.as_ref(), FieldType::OneOf(_)) => {}
+                    (field_ty, field_value) => {
+                        result.insert(
+                            json_name(&field.name),
+                            to_proto3(config, field_ty, field_value),
+                        );
+                    }
+                }
+            }
+            Value::Object(result)
+        }
+        FieldType::Option(inner_ty) => match value {
+            Value::Null => Value::Null,
+            value => to_proto3(config, inner_ty, value),
+        },
+        FieldType::Array(value_ty) => match value {
+            Value::Array(value) => value
+                .into_iter()
+                .map(|element| to_proto3(config, value_ty, element))
+                .collect(),
+            value => value,
+        },
+        FieldType::Map(_, value_field) => match value {
+            Value::Object(value) => value
+                .into_iter()
+                .map(|(key, entry)| (key, to_proto3(config, &value_field.ty, entry)))
+                .collect(),
+            value => value,
+        },
+        FieldType::OneOf(variants) => match value {
+            Value::Object(value) => value
+                .into_iter()
+                .map(|(name, variant_value)| {
+                    let variant_value = match variants.iter().find(|v| v.name == name) {
+                        Some(variant) => to_proto3(config, &variant.ty, variant_value),
+                        None => variant_value,
+                    };
+                    (json_name(&name), variant_value)
+                })
+                .collect(),
+            value => value,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::json_name;
+
+    #[test]
+    fn test_json_name() {
+        assert_eq!(json_name("foo_bar"), "fooBar");
+        assert_eq!(json_name("foo"), "foo");
+        assert_eq!(json_name("foo_bar_2"), "fooBar2");
+        assert_eq!(json_name("felt252_x"), "felt252X");
+    }
+}
@@ -0,0 +1,610 @@
+//! Conversion between Cairo Serde felts and the Protobuf binary wire format.
+//!
+//! Felts are first read into the same JSON the oracle server would receive, so that the range
+//! checks and `ByteArray` handling are shared with the JSON transport.
+
+use base64::prelude::*;
+use prost::bytes::{Buf, BufMut};
+use prost::encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType};
+use serde_json::{Map, Value};
+use starknet_types_core::felt::Felt as Felt252;
+
+use crate::configuration::{Configuration, Field, FieldType, IntegerEncoding, PrimitiveType, Tag};
+use crate::{
+    deserialize_field, format_map_key, invalid_value, message_config, parse_bytes, parse_integer,
+    parse_map_key, proto3, root_path, serialize_field, SerdeError,
+};
+
+/// A single field read off the wire, before its type is known.
+#[derive(Clone, Copy, Debug)]
+enum Record<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    Bytes(&'a [u8]),
+}
+
+fn invalid_protobuf(path: &str, reason: impl ToString) -> SerdeError {
+    SerdeError::InvalidProtobuf {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Narrows an integer read off the wire to the Rust type of `ty`, failing when it doesn't fit.
+fn narrow<T: TryFrom<i128>>(n: i128, ty: &FieldType, path: &str) -> Result<T, SerdeError> {
+    T::try_from(n).map_err(|_| invalid_value(path, ty.clone(), n))
+}
+
+fn field_tag<'a>(field: &'a Field, path: &str) -> Result<&'a Tag, SerdeError> {
+    field
+        .tag
+        .as_ref()
+        .ok_or_else(|| SerdeError::MissingFieldNumber {
+            path: path.to_string(),
+        })
+}
+
+/// Whether repeated values of the type are packed into a single length-delimited record.
+fn is_packable(ty: &FieldType) -> bool {
+    matches!(
+        ty,
+        FieldType::Enum(_)
+            | FieldType::Primitive(
+                PrimitiveType::U32
+                    | PrimitiveType::U64
+                    | PrimitiveType::I32
+                    | PrimitiveType::I64
+                    | PrimitiveType::BOOL
+            )
+    )
+}
+
+/// Reads Cairo Serde felts of type `ty`, which must be a message, and encodes them in the
+/// Protobuf binary wire format.
+pub fn encode_protobuf(
+    config: &Configuration,
+    ty: &FieldType,
+    value: &mut &[Felt252],
+) -> Result<Vec<u8>, SerdeError> {
+    let path = root_path(ty);
+    let FieldType::Message(message_ty) = ty else {
+        return Err(invalid_protobuf(&path, "only messages can be encoded"));
+    };
+    let value = deserialize_field(config, ty, value, &path)?;
+    let mut buf = Vec::new();
+    encode_message(config, message_ty, &value, &path, &mut buf)?;
+    Ok(buf)
+}
+
+/// Decodes a message of type `ty` from the Protobuf binary wire format into Cairo Serde felts.
+pub fn decode_protobuf(
+    config: &Configuration,
+    ty: &FieldType,
+    bytes: &[u8],
+) -> Result<Vec<Felt252>, SerdeError> {
+    let path = root_path(ty);
+    let FieldType::Message(message_ty) = ty else {
+        return Err(invalid_protobuf(&path, "only messages can be decoded"));
+    };
+    let value = decode_message(config, message_ty, bytes, &path)?;
+    serialize_field(config, ty, &value, &path)
+}
+
+fn encode_message(
+    config: &Configuration,
+    message_ty: &str,
+    value: &Value,
+    path: &str,
+    buf: &mut Vec<u8>,
+) -> Result<(), SerdeError> {
+    for field in message_config(config, message_ty, path)? {
+        let field_path = format!("{path}.{}", field.name);
+        let field_value = value.get(&field.name).unwrap_or(&Value::Null);
+        match &field.ty {
+            FieldType::Option(inner_ty) => match (inner_ty.as_ref(), field_value) {
+                (_, Value::Null) => {}
+                (FieldType::OneOf(variants), Value::Object(set)) => {
+                    for (name, variant_value) in set {
+                        let variant_path = format!("{path}.{name}");
+                        let variant = variants
+                            .iter()
+                            .find(|variant| variant.name == *name)
+                            .ok_or_else(|| {
+                                invalid_protobuf(&variant_path, "unknown oneof field")
+                            })?;
+                        let tag = field_tag(variant, &variant_path)?;
+                        encode_value(
+                            config,
+                            tag,
+                            &variant.ty,
+                            variant_value,
+                            true,
+                            &variant_path,
+                            buf,
+                        )?;
+                    }
+                }
+                (inner_ty, field_value) => {
+                    let tag = field_tag(field, &field_path)?;
+                    encode_value(config, tag, inner_ty, field_value, true, &field_path, buf)?;
+                }
+            },
+            FieldType::Array(element_ty) => {
+                let tag = field_tag(field, &field_path)?;
+                let elements = field_value
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if is_packable(element_ty) {
+                    if elements.is_empty() {
+                        continue;
+                    }
+                    let mut packed = Vec::new();
+                    for (idx, element) in elements.iter().enumerate() {
+                        let element_path = format!("{field_path}[{idx}]");
+                        encode_scalar(tag, element_ty, element, &element_path, &mut packed)?;
+                    }
+                    encode_key(tag.number, WireType::LengthDelimited, buf);
+                    encode_varint(packed.len() as u64, buf);
+                    buf.put_slice(&packed);
+                } else {
+                    for (idx, element) in elements.iter().enumerate() {
+                        let element_path = format!("{field_path}[{idx}]");
+                        encode_value(config, tag, element_ty, element, true, &element_path, buf)?;
+                    }
+                }
+            }
+            FieldType::Map(key_field, value_field) => {
+                let tag = field_tag(field, &field_path)?;
+                let key_tag = field_tag(key_field, &field_path)?;
+                let value_tag = field_tag(value_field, &field_path)?;
+                let entries = field_value.as_object().into_iter().flatten();
+                for (key, entry) in entries {
+                    let entry_path = format!("{field_path}[{key}]");
+                    let mut entry_buf = Vec::new();
+                    let key = parse_map_key(key, &key_field.ty);
+                    encode_value(
+                        config,
+                        key_tag,
+                        &key_field.ty,
+                        &key,
+                        true,
+                        &entry_path,
+                        &mut entry_buf,
+                    )?;
+                    encode_value(
+                        config,
+                        value_tag,
+                        &value_field.ty,
+                        entry,
+                        true,
+                        &entry_path,
+                        &mut entry_buf,
+                    )?;
+                    encode_key(tag.number, WireType::LengthDelimited, buf);
+                    encode_varint(entry_buf.len() as u64, buf);
+                    buf.put_slice(&entry_buf);
+                }
+            }
+            ty => {
+                let tag = field_tag(field, &field_path)?;
+                encode_value(config, tag, ty, field_value, false, &field_path, buf)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Encodes a single, non-repeated value. Scalars without explicit presence are skipped when
+/// they hold the default value, as proto3 does.
+fn encode_value(
+    config: &Configuration,
+    tag: &Tag,
+    ty: &FieldType,
+    value: &Value,
+    explicit: bool,
+    path: &str,
+    buf: &mut Vec<u8>,
+) -> Result<(), SerdeError> {
+    let length_delimited = |buf: &mut Vec<u8>, bytes: &[u8]| {
+        encode_key(tag.number, WireType::LengthDelimited, buf);
+        encode_varint(bytes.len() as u64, buf);
+        buf.put_slice(bytes);
+    };
+
+    match ty {
+        ty if is_packable(ty) => {
+            let mut scalar = Vec::new();
+            encode_scalar(tag, ty, value, path, &mut scalar)?;
+            let is_default = scalar.iter().all(|b| *b == 0);
+            if explicit || !is_default {
+                let wire_type = match (tag.encoding, ty) {
+                    (IntegerEncoding::Fixed, FieldType::Primitive(PrimitiveType::U32))
+                    | (IntegerEncoding::Fixed, FieldType::Primitive(PrimitiveType::I32)) => {
+                        WireType::ThirtyTwoBit
+                    }
+                    (IntegerEncoding::Fixed, _) => WireType::SixtyFourBit,
+                    _ => WireType::Varint,
+                };
+                encode_key(tag.number, wire_type, buf);
+                buf.put_slice(&scalar);
+            }
+        }
+        FieldType::Primitive(PrimitiveType::BYTEARRAY | PrimitiveType::FELT252) => {
+            let s = value
+                .as_str()
+                .ok_or_else(|| invalid_protobuf(path, "expected a string"))?;
+            if explicit || !s.is_empty() {
+                length_delimited(buf, s.as_bytes());
+            }
+        }
+        FieldType::Primitive(PrimitiveType::BYTES) => {
+            let bytes = value
+                .as_str()
+                .and_then(parse_bytes)
+                .ok_or_else(|| invalid_protobuf(path, "expected bytes"))?;
+            if explicit || !bytes.is_empty() {
+                length_delimited(buf, &bytes);
+            }
+        }
+        // Types from `cairo/types.proto`, messages with a single `value` field.
+        FieldType::Primitive(primitive) => {
+            let value_tag = Tag {
+                number: 1,
+                encoding: IntegerEncoding::Varint,
+            };
+            let mut wrapper = Vec::new();
+            let value_ty = match primitive {
+                PrimitiveType::U8 | PrimitiveType::U16 => PrimitiveType::U32,
+                PrimitiveType::I8 | PrimitiveType::I16 => PrimitiveType::I32,
+                _ => PrimitiveType::BYTEARRAY,
+            };
+            let value = match value_ty {
+                PrimitiveType::BYTEARRAY => {
+                    let v = parse_integer(value)
+                        .ok_or_else(|| invalid_protobuf(path, "expected an integer"))?;
+                    Value::from(v.to_string())
+                }
+                _ => value.clone(),
+            };
+            encode_value(
+                config,
+                &value_tag,
+                &FieldType::Primitive(value_ty),
+                &value,
+                false,
+                path,
+                &mut wrapper,
+            )?;
+            length_delimited(buf, &wrapper);
+        }
+        FieldType::Message(message_ty) => {
+            let mut message = Vec::new();
+            encode_message(config, message_ty, value, path, &mut message)?;
+            length_delimited(buf, &message);
+        }
+        _ => {
+            return Err(invalid_protobuf(
+                path,
+                "type can't be nested in a Protobuf field",
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Encodes the payload, without the key, of an integer, `bool` or enum value.
+fn encode_scalar(
+    tag: &Tag,
+    ty: &FieldType,
+    value: &Value,
+    path: &str,
+    buf: &mut Vec<u8>,
+) -> Result<(), SerdeError> {
+    let n = match value {
+        Value::Bool(b) => i128::from(*b),
+        value => parse_integer(value)
+            .and_then(|n| i128::try_from(n).ok())
+            .ok_or_else(|| invalid_protobuf(path, "expected an integer"))?,
+    };
+    let is_32_bit = matches!(
+        ty,
+        FieldType::Primitive(PrimitiveType::U32 | PrimitiveType::I32)
+    );
+    match tag.encoding {
+        IntegerEncoding::Fixed if is_32_bit => buf.put_u32_le(n as u32),
+        IntegerEncoding::Fixed => buf.put_u64_le(n as u64),
+        IntegerEncoding::ZigZag => {
+            let n = n as i64;
+            encode_varint(((n << 1) ^ (n >> 63)) as u64, buf)
+        }
+        // Negative `int32` values are sign-extended to 64 bits.
+        IntegerEncoding::Varint => encode_varint(n as i64 as u64, buf),
+    }
+    Ok(())
+}
+
+/// Splits a message into its records, in the order they appear on the wire.
+fn read_records<'a>(mut bytes: &'a [u8], path: &str) -> Result<Vec<(u32, Record<'a>)>, SerdeError> {
+    let mut records = Vec::new();
+    while bytes.has_remaining() {
+        let (number, wire_type) = decode_key(&mut bytes).map_err(|e| invalid_protobuf(path, e))?;
+        let record = match wire_type {
+            WireType::Varint => {
+                Record::Varint(decode_varint(&mut bytes).map_err(|e| invalid_protobuf(path, e))?)
+            }
+            WireType::SixtyFourBit if bytes.remaining() >= 8 => Record::Fixed64(bytes.get_u64_le()),
+            WireType::ThirtyTwoBit if bytes.remaining() >= 4 => Record::Fixed32(bytes.get_u32_le()),
+            WireType::LengthDelimited => {
+                let len = decode_varint(&mut bytes).map_err(|e| invalid_protobuf(path, e))?;
+                let len = usize::try_from(len)
+                    .ok()
+                    .filter(|len| *len <= bytes.len())
+                    .ok_or_else(|| invalid_protobuf(path, "buffer underflow"))?;
+                let (record, rest) = bytes.split_at(len);
+                bytes = rest;
+                Record::Bytes(record)
+            }
+            WireType::SixtyFourBit | WireType::ThirtyTwoBit => {
+                return Err(invalid_protobuf(path, "buffer underflow"))
+            }
+            WireType::StartGroup | WireType::EndGroup => {
+                return Err(invalid_protobuf(path, "groups are not supported"))
+            }
+        };
+        records.push((number, record));
+    }
+    Ok(records)
+}
+
+/// Decodes a message into the JSON the Cairo Serde serializer expects. Fields missing from the
+/// wire take their default value.
+fn decode_message(
+    config: &Configuration,
+    message_ty: &str,
+    bytes: &[u8],
+    path: &str,
+) -> Result<Value, SerdeError> {
+    let records = read_records(bytes, path)?;
+    let with_number = |number: u32| {
+        records
+            .iter()
+            .filter(move |(n, _)| *n == number)
+            .map(|(_, record)| *record)
+    };
+
+    let mut result = Map::new();
+    for field in message_config(config, message_ty, path)? {
+        let field_path = format!("{path}.{}", field.name);
+        let field_value = match &field.ty {
+            FieldType::Option(inner_ty) => match inner_ty.as_ref() {
+                FieldType::OneOf(variants) => {
+                    // The last oneof field on the wire wins.
+                    let mut set = None;
+                    for (number, record) in &records {
+                        if let Some(variant) = variants.iter().find(|variant| {
+                            variant.tag.as_ref().map(|tag| tag.number) == Some(*number)
+                        }) {
+                            set = Some((variant, *record));
+                        }
+                    }
+                    match set {
+                        Some((variant, record)) => {
+                            let variant_path = format!("{path}.{}", variant.name);
+                            let tag = field_tag(variant, &variant_path)?;
+                            let variant_value =
+                                decode_value(config, tag, &variant.ty, record, &variant_path)?;
+                            Value::Object(Map::from_iter([(variant.name.clone(), variant_value)]))
+                        }
+                        None => Value::Null,
+                    }
+                }
+                inner_ty => {
+                    let tag = field_tag(field, &field_path)?;
+                    match with_number(tag.number).last() {
+                        Some(record) => decode_value(config, tag, inner_ty, record, &field_path)?,
+                        None => Value::Null,
+                    }
+                }
+            },
+            FieldType::Array(element_ty) => {
+                let tag = field_tag(field, &field_path)?;
+                let mut elements = Vec::new();
+                for record in with_number(tag.number) {
+                    let element_path = format!("{field_path}[{}]", elements.len());
+                    match record {
+                        // Packed scalars, parsers must accept both packed and unpacked values.
+                        Record::Bytes(mut packed) if is_packable(element_ty) => {
+                            while packed.has_remaining() {
+                                let record = match (tag.encoding, element_ty.as_ref()) {
+                                    (
+                                        IntegerEncoding::Fixed,
+                                        FieldType::Primitive(
+                                            PrimitiveType::U32 | PrimitiveType::I32,
+                                        ),
+                                    ) if packed.remaining() >= 4 => {
+                                        Record::Fixed32(packed.get_u32_le())
+                                    }
+                                    (IntegerEncoding::Fixed, _) if packed.remaining() >= 8 => {
+                                        Record::Fixed64(packed.get_u64_le())
+                                    }
+                                    (IntegerEncoding::Fixed, _) => {
+                                        return Err(invalid_protobuf(
+                                            &element_path,
+                                            "buffer underflow",
+                                        ))
+                                    }
+                                    _ => Record::Varint(
+                                        decode_varint(&mut packed)
+                                            .map_err(|e| invalid_protobuf(&element_path, e))?,
+                                    ),
+                                };
+                                let element_path = format!("{field_path}[{}]", elements.len());
+                                elements.push(decode_value(
+                                    config,
+                                    tag,
+                                    element_ty,
+                                    record,
+                                    &element_path,
+                                )?);
+                            }
+                        }
+                        record => elements.push(decode_value(
+                            config,
+                            tag,
+                            element_ty,
+                            record,
+                            &element_path,
+                        )?),
+                    }
+                }
+                Value::Array(elements)
+            }
+            FieldType::Map(key_field, value_field) => {
+                let tag = field_tag(field, &field_path)?;
+                let mut entries = Map::new();
+                for record in with_number(tag.number) {
+                    let Record::Bytes(entry) = record else {
+                        return Err(invalid_protobuf(&field_path, "expected a map entry"));
+                    };
+                    let entry_records = read_records(entry, &field_path)?;
+                    let decode_entry_field = |entry_field: &Field| {
+                        let entry_tag = field_tag(entry_field, &field_path)?;
+                        match entry_records
+                            .iter()
+                            .rev()
+                            .find(|(n, _)| *n == entry_tag.number)
+                        {
+                            Some((_, record)) => decode_value(
+                                config,
+                                entry_tag,
+                                &entry_field.ty,
+                                *record,
+                                &field_path,
+                            ),
+                            None => Ok(proto3::default_value(config, &entry_field.ty)),
+                        }
+                    };
+                    let key = decode_entry_field(key_field)?;
+                    let value = decode_entry_field(value_field)?;
+                    entries.insert(format_map_key(key), value);
+                }
+                Value::Object(entries)
+            }
+            ty => {
+                let tag = field_tag(field, &field_path)?;
+                match with_number(tag.number).last() {
+                    Some(record) => decode_value(config, tag, ty, record, &field_path)?,
+                    None => proto3::default_value(config, ty),
+                }
+            }
+        };
+        result.insert(field.name.clone(), field_value);
+    }
+    Ok(Value::Object(result))
+}
+
+/// Decodes a single record of a non-repeated type.
+fn decode_value(
+    config: &Configuration,
+    tag: &Tag,
+    ty: &FieldType,
+    record: Record,
+    path: &str,
+) -> Result<Value, SerdeError> {
+    let integer = |record: Record| -> Result<i128, SerdeError> {
+        let is_signed = matches!(
+            ty,
+            FieldType::Enum(_) | FieldType::Primitive(PrimitiveType::I32 | PrimitiveType::I64)
+        );
+        match (record, tag.encoding) {
+            (Record::Varint(v), IntegerEncoding::ZigZag) => {
+                Ok(i128::from((v >> 1) as i64 ^ -((v & 1) as i64)))
+            }
+            (Record::Varint(v), _) if is_signed => Ok(i128::from(v as i64)),
+            (Record::Varint(v), _) => Ok(i128::from(v)),
+            (Record::Fixed32(v), _) if is_signed => Ok(i128::from(v as i32)),
+            (Record::Fixed32(v), _) => Ok(i128::from(v)),
+            (Record::Fixed64(v), _) if is_signed => Ok(i128::from(v as i64)),
+            (Record::Fixed64(v), _) => Ok(i128::from(v)),
+            (Record::Bytes(_), _) => Err(invalid_protobuf(path, "expected an integer")),
+        }
+    };
+    let bytes = |record: Record<'_>| match record {
+        Record::Bytes(bytes) => Ok(bytes.to_vec()),
+        _ => Err(invalid_protobuf(path, "expected a length-delimited field")),
+    };
+
+    let value = match ty {
+        FieldType::Primitive(PrimitiveType::BOOL) => Value::from(integer(record)? != 0),
+        FieldType::Primitive(PrimitiveType::I32) | FieldType::Enum(_) => {
+            // `int32` values are sign-extended to 64 bits on the wire.
+            Value::from(narrow::<i32>(integer(record)?, ty, path)?)
+        }
+        FieldType::Primitive(PrimitiveType::U32) => {
+            Value::from(narrow::<u32>(integer(record)?, ty, path)?)
+        }
+        FieldType::Primitive(PrimitiveType::I64) => {
+            Value::from(narrow::<i64>(integer(record)?, ty, path)?)
+        }
+        FieldType::Primitive(PrimitiveType::U64) => {
+            Value::from(narrow::<u64>(integer(record)?, ty, path)?)
+        }
+        FieldType::Primitive(PrimitiveType::BYTEARRAY | PrimitiveType::FELT252) => {
+            let s = String::from_utf8(bytes(record)?)
+                .map_err(|_| invalid_protobuf(path, "string is not valid UTF-8"))?;
+            Value::from(s)
+        }
+        FieldType::Primitive(PrimitiveType::BYTES) => {
+            Value::from(BASE64_STANDARD.encode(bytes(record)?))
+        }
+        // Types from `cairo/types.proto`, messages with a single `value` field.
+        FieldType::Primitive(primitive) => {
+            let wrapper = bytes(record)?;
+            let value = read_records(&wrapper, path)?
+                .into_iter()
+                .filter(|(number, _)| *number == 1)
+                .last()
+                .map(|(_, record)| record);
+            let value_tag = Tag {
+                number: 1,
+                encoding: IntegerEncoding::Varint,
+            };
+            match (primitive, value) {
+                (_, None) => proto3::default_value(config, ty),
+                (PrimitiveType::U8 | PrimitiveType::U16, Some(record)) => decode_value(
+                    config,
+                    &value_tag,
+                    &FieldType::Primitive(PrimitiveType::U32),
+                    record,
+                    path,
+                )?,
+                (PrimitiveType::I8 | PrimitiveType::I16, Some(record)) => decode_value(
+                    config,
+                    &value_tag,
+                    &FieldType::Primitive(PrimitiveType::I32),
+                    record,
+                    path,
+                )?,
+                (_, Some(record)) => decode_value(
+                    config,
+                    &value_tag,
+                    &FieldType::Primitive(PrimitiveType::BYTEARRAY),
+                    record,
+                    path,
+                )?,
+            }
+        }
+        FieldType::Message(message_ty) => {
+            decode_message(config, message_ty, &bytes(record)?, path)?
+        }
+        _ => {
+            return Err(invalid_protobuf(
+                path,
+                "type can't be nested in a Protobuf field",
+            ))
+        }
+    };
+    Ok(value)
+}
@@ -5,7 +5,7 @@ const port = 3000;
 
 app.use(express.json());
 
-app.post('/sqrt', (req, res) => {
+app.post('/SqrtOracle/sqrt', (req, res) => {
     console.dir(`received payload ${JSON.stringify(req.body)}`);
     n = Math.sqrt(req.body.n);
     res.statusCode = 200;
@@ -12,7 +12,7 @@ pub impl SqrtOracle of SqrtOracleTrait {
     fn sqrt(arg: super::oracle::Request) -> super::oracle::Response {
         let mut serialized = ArrayTrait::new();
         arg.serialize(ref serialized);
-        let mut result = cheatcode::<'sqrt'>(serialized.span());
+        let mut result = cheatcode::<'SqrtOracle/sqrt'>(serialized.span());
         Serde::deserialize(ref result).unwrap()
     }
 }
@@ -27,7 +27,7 @@ async fn main() {
         .init();
 
     let app = Router::new()
-        .route("/shirt", post(root))
+        .route("/ShirtsOracle/shirt", post(root))
         .layer(TraceLayer::new_for_http());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
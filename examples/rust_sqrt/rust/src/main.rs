@@ -29,7 +29,7 @@ async fn main() {
         .init();
 
     let app = Router::new()
-        .route("/sqrt", post(root))
+        .route("/SqrtOracle/sqrt", post(root))
         .layer(TraceLayer::new_for_http());
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")